@@ -0,0 +1,71 @@
+//! Compares the time taken to prove with the polynomial range-check gate against the
+//! lookup-table range check. Each circuit is proven at its own smallest `k`, and its max
+//! constraint degree is part of the benchmark id, e.g. `prove sudoku/lookup/degree=3`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use halo2wrong::halo2::{
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::{create_proof, keygen_pk, keygen_vk},
+    poly::kzg::{
+        commitment::{KZGCommitmentScheme, ParamsKZG},
+        multiopen::ProverSHPLONK,
+    },
+    transcript::{Blake2bWrite, Challenge255, TranscriptWriterBuffer},
+};
+use rand::rngs::OsRng;
+use sudoku_halo2::{
+    board::PuzzleReader,
+    public_inputs,
+    range::{LookupRangeCheck, PolynomialRangeCheck, RangeCheckKind},
+    Puzzle, Solution, SudokuCircuit,
+};
+
+// example_board reads the puzzle and solution shared with the tests
+fn example_board() -> (Puzzle, Solution) {
+    let example = include_str!("../puzzles/example.txt");
+    let mut boards = PuzzleReader::new(example.as_bytes()).map(Result::unwrap);
+    let puzzle = boards.next().unwrap();
+    let solution = Solution::new(boards.next().unwrap().cells().clone()).unwrap();
+    (puzzle, solution)
+}
+
+fn bench_range_check<R: RangeCheckKind>(c: &mut Criterion, name: &str) {
+    let (puzzle, solution) = example_board();
+    let instance_columns = public_inputs(&puzzle, None);
+    let instances: Vec<&[Fr]> = instance_columns.iter().map(Vec::as_slice).collect();
+    let circuit: SudokuCircuit<Fr, 9, R> = SudokuCircuit::new(puzzle, solution).unwrap();
+    let stats = circuit.stats().expect("the circuit should lay out");
+
+    let params = ParamsKZG::<Bn256>::setup(stats.min_k, OsRng);
+    let vk = keygen_vk(&params, &circuit).expect("keygen_vk should not fail");
+    let pk = keygen_pk(&params, vk, &circuit).expect("keygen_pk should not fail");
+
+    let id = format!("prove sudoku/{}/degree={}", name, stats.max_degree);
+    c.bench_function(&id, |b| {
+        b.iter(|| {
+            let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+            create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<'_, Bn256>, _, _, _, _>(
+                &params,
+                &pk,
+                &[circuit.clone()],
+                &[instances.as_slice()],
+                OsRng,
+                &mut transcript,
+            )
+            .expect("proof generation should not fail");
+            transcript.finalize()
+        })
+    });
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    bench_range_check::<PolynomialRangeCheck>(c, "polynomial");
+    bench_range_check::<LookupRangeCheck>(c, "lookup");
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = criterion_benchmark
+}
+criterion_main!(benches);
@@ -0,0 +1,104 @@
+//! Benchmarks each stage of proving a sudoku solution: laying the circuit out under the mock
+//! prover, generating the verifying and proving keys, proving and verifying. Every stage is
+//! run for each combination of group check and range check, so that changes to the
+//! constraints show up as regressions in the strategies they touch.
+//!
+//! Run a subset with e.g. `cargo bench --bench sudoku -- "create_proof/fingerprint"`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use halo2wrong::halo2::{
+    dev::MockProver,
+    halo2curves::bn256::{Bn256, Fr},
+    plonk::Circuit,
+    poly::kzg::commitment::ParamsKZG,
+};
+use rand::rngs::OsRng;
+use sudoku_halo2::{
+    board::PuzzleReader,
+    commitment::PuzzleCells,
+    group::{
+        Fingerprint, GroupCheckStrategy, IndexHint, MultisetProduct, PairwiseDistinct, Shuffle,
+    },
+    public_inputs,
+    range::{LookupRangeCheck, PolynomialRangeCheck, RangeCheckKind},
+    CommitmentParams, Solution, SudokuCircuit,
+};
+
+// circuit builds a circuit around the puzzle and solution shared with the tests
+fn circuit<R: RangeCheckKind, G: GroupCheckStrategy>() -> SudokuCircuit<Fr, 9, R, PuzzleCells, G> {
+    let example = include_str!("../puzzles/example.txt");
+    let mut boards = PuzzleReader::new(example.as_bytes()).map(Result::unwrap);
+    let puzzle = boards.next().unwrap();
+    let solution = Solution::new(boards.next().unwrap().cells().clone()).unwrap();
+    SudokuCircuit::new(puzzle, solution).unwrap()
+}
+
+fn bench_circuit<R: RangeCheckKind, G: GroupCheckStrategy>(c: &mut Criterion, name: &str) {
+    let circuit = circuit::<R, G>();
+    let k = circuit.min_k().unwrap();
+    let instance_columns = public_inputs(circuit.puzzle(), circuit.solver_id());
+    let instances: Vec<&[Fr]> = instance_columns.iter().map(Vec::as_slice).collect();
+
+    c.bench_with_input(
+        BenchmarkId::new("mock_prover", name),
+        &circuit,
+        |b, circuit| b.iter(|| MockProver::run(k, circuit, instance_columns.clone()).unwrap()),
+    );
+
+    let params = ParamsKZG::<Bn256>::setup_params(k, OsRng);
+    let keygen_circuit = circuit.without_witnesses();
+    c.bench_with_input(
+        BenchmarkId::new("keygen_vk", name),
+        &keygen_circuit,
+        |b, circuit| b.iter(|| params.keygen_vk(circuit).unwrap()),
+    );
+
+    let vk = params.keygen_vk(&keygen_circuit).unwrap();
+    c.bench_with_input(
+        BenchmarkId::new("keygen_pk", name),
+        &keygen_circuit,
+        |b, circuit| b.iter(|| params.keygen_pk(vk.clone(), circuit).unwrap()),
+    );
+
+    let pk = params.keygen_pk(vk.clone(), &keygen_circuit).unwrap();
+    c.bench_with_input(
+        BenchmarkId::new("create_proof", name),
+        &circuit,
+        |b, circuit| {
+            b.iter(|| {
+                params
+                    .create_proof(&pk, circuit.clone(), &instances, OsRng)
+                    .unwrap()
+            })
+        },
+    );
+
+    let proof = params
+        .create_proof(&pk, circuit.clone(), &instances, OsRng)
+        .unwrap();
+    c.bench_with_input(
+        BenchmarkId::new("verify_proof", name),
+        &proof,
+        |b, proof| b.iter(|| params.verify_proof(&vk, &instances, proof).unwrap()),
+    );
+}
+
+fn bench_group_check<G: GroupCheckStrategy>(c: &mut Criterion, name: &str) {
+    bench_circuit::<PolynomialRangeCheck, G>(c, &format!("{}, polynomial range check", name));
+    bench_circuit::<LookupRangeCheck, G>(c, &format!("{}, lookup range check", name));
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    bench_group_check::<PairwiseDistinct>(c, "pairwise distinct");
+    bench_group_check::<Fingerprint>(c, "fingerprint");
+    bench_group_check::<Shuffle>(c, "shuffle");
+    bench_group_check::<IndexHint>(c, "index hint");
+    bench_group_check::<MultisetProduct>(c, "multiset product");
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = criterion_benchmark
+}
+criterion_main!(benches);
@@ -0,0 +1,66 @@
+//! Renders the layout of `SudokuCircuit` for review: which rows each region takes up, and
+//! which columns it assigns to.
+//!
+//! ```sh
+//! cargo run --example layout -- target/layout
+//! ```
+//!
+//! writes `layout.png` and `layout.svg`, with the regions laid out by `synthesize` ("load
+//! boards", then one region per cell checked against its clue and per row, column and box
+//! checked) labelled by name, and
+//! `layout.dot`, the graph of the circuit's gates and the columns they query, for Graphviz
+//! (`dot -Tsvg layout.dot`).
+
+use halo2::dev::{circuit_dot_graph, CircuitLayout};
+use halo2wrong::halo2::halo2curves::bn256::Fr;
+use plotters::prelude::*;
+use std::{env, fs, path::PathBuf};
+use sudoku_halo2::{
+    board::{PuzzleReader, ReadPuzzleError},
+    Solution, SudokuCircuit, K,
+};
+
+const WIDTH: u32 = 1024;
+const HEIGHT: u32 = 3072;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let out: PathBuf = env::args_os()
+        .nth(1)
+        .map_or_else(|| "target/layout".into(), PathBuf::from);
+    fs::create_dir_all(&out)?;
+
+    // the example puzzle shared with the tests, followed by its solution
+    let example = include_str!("../puzzles/example.txt");
+    let mut boards = PuzzleReader::new(example.as_bytes());
+    let puzzle = boards.next().ok_or(ReadPuzzleError::NoPuzzle)??;
+    let solution = boards.next().ok_or(ReadPuzzleError::NoPuzzle)??;
+    let solution = Solution::new(solution.cells().clone())?;
+    let circuit = SudokuCircuit::<Fr>::new(puzzle, solution)?;
+    let layout = || {
+        CircuitLayout::default()
+            .show_labels(true)
+            .mark_equality_cells(true)
+    };
+
+    let png = out.join("layout.png");
+    let root = BitMapBackend::new(&png, (WIDTH, HEIGHT)).into_drawing_area();
+    root.fill(&WHITE)?;
+    let root = root.titled("Sudoku circuit layout", ("sans-serif", 40))?;
+    layout().render(K, &circuit, &root)?;
+    root.present()?;
+
+    let svg = out.join("layout.svg");
+    let root = SVGBackend::new(&svg, (WIDTH, HEIGHT)).into_drawing_area();
+    root.fill(&WHITE)?;
+    let root = root.titled("Sudoku circuit layout", ("sans-serif", 40))?;
+    layout().render(K, &circuit, &root)?;
+    root.present()?;
+
+    let dot = out.join("layout.dot");
+    fs::write(&dot, circuit_dot_graph(&circuit))?;
+
+    for path in [png, svg, dot] {
+        println!("wrote {}", path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,212 @@
+//! Command-line key generation, proving and verification of sudoku solutions.
+//!
+//! ```sh
+//! sudoku-halo2 keygen --k 12 --out keys/
+//! sudoku-halo2 prove --puzzle p.txt --solution s.txt --vk keys/vk.bin --out proof.bin
+//! sudoku-halo2 verify --puzzle p.txt --vk keys/vk.bin --proof proof.bin
+//! sudoku-halo2 stats
+//! ```
+//!
+//! Boards are read in the one-line format (see `sudoku_halo2::board`), from the first line of
+//! the file that isn't blank or a `#` comment. Parameters are read from `params.bin` next to
+//! the key unless `--params` is given.
+
+use clap::{Parser, Subcommand};
+use ff::PrimeField;
+use halo2wrong::halo2::{
+    halo2curves::bn256::{Bn256, Fr},
+    poly::kzg::commitment::ParamsKZG,
+};
+use rand::rngs::OsRng;
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+use sudoku_halo2::{
+    board::{PuzzleReader, ReadPuzzleError},
+    keygen, keys, prove, setup, verify, CommitmentParams, Puzzle, Solution, SudokuCircuit,
+    SudokuError,
+};
+
+// Exit codes, besides 0 for success and clap's 2 for bad usage
+const EXIT_INVALID_PROOF: u8 = 1;
+const EXIT_BAD_INPUT: u8 = 3;
+const EXIT_PROVER_FAILED: u8 = 4;
+
+const PARAMS_FILE: &str = "params.bin";
+const VK_FILE: &str = "vk.bin";
+
+#[derive(Parser)]
+#[command(
+    name = "sudoku-halo2",
+    about = "Prove and verify knowledge of sudoku solutions",
+    after_help = "Exit codes: 0 on success, 1 if the proof doesn't verify, 2 on bad usage, \
+                  3 if an input file can't be read or is invalid, 4 if key generation or proving \
+                  fails."
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate parameters and a verifying key. The parameters come from a local, insecure trusted
+    /// setup, which is only fit for testing
+    Keygen {
+        /// log2 of the number of rows of the circuit, at least the circuit's smallest k (see
+        /// `stats`). Defaults to that smallest k
+        #[arg(long)]
+        k: Option<u32>,
+        /// Directory to write params.bin and vk.bin to
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Prove knowledge of a solution to a puzzle
+    Prove {
+        #[arg(long)]
+        puzzle: PathBuf,
+        /// Defaults to solving the puzzle, if it has a unique solution
+        #[arg(long)]
+        solution: Option<PathBuf>,
+        /// The proving key is rebuilt from this verifying key, as proving keys can't be saved
+        #[arg(long)]
+        vk: PathBuf,
+        /// Defaults to params.bin next to the verifying key
+        #[arg(long)]
+        params: Option<PathBuf>,
+        /// Binds the proof to this solver, as a decimal number
+        #[arg(long, value_parser = parse_solver_id)]
+        solver_id: Option<Fr>,
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Verify a proof of knowledge of a solution to a puzzle
+    Verify {
+        #[arg(long)]
+        puzzle: PathBuf,
+        #[arg(long)]
+        vk: PathBuf,
+        /// Defaults to params.bin next to the verifying key
+        #[arg(long)]
+        params: Option<PathBuf>,
+        /// The solver the proof was made for, as a decimal number
+        #[arg(long, value_parser = parse_solver_id)]
+        solver_id: Option<Fr>,
+        #[arg(long)]
+        proof: PathBuf,
+    },
+    /// Report the rows each phase of the circuit uses, its columns, degree and smallest k
+    Stats {
+        /// Also list the rows of every region
+        #[arg(long)]
+        regions: bool,
+    },
+}
+
+// read_puzzle reads the first puzzle in the file, skipping blank lines and comments
+fn read_puzzle(path: &Path) -> Result<Puzzle, SudokuError> {
+    let mut reader = PuzzleReader::new(BufReader::new(File::open(path)?));
+    Ok(reader.next().unwrap_or(Err(ReadPuzzleError::NoPuzzle))?)
+}
+
+// read_solution reads a solution in the same format as read_puzzle
+fn read_solution(path: &Path) -> Result<Solution, SudokuError> {
+    Ok(Solution::new(read_puzzle(path)?.cells().clone())?)
+}
+
+fn parse_solver_id(solver_id: &str) -> Result<Fr, String> {
+    Fr::from_str_vartime(solver_id).ok_or_else(|| "expected a decimal number".to_string())
+}
+
+fn load_params(params: Option<PathBuf>, key: &Path) -> Result<ParamsKZG<Bn256>, SudokuError> {
+    let path = params.unwrap_or_else(|| key.with_file_name(PARAMS_FILE));
+    Ok(keys::load_params(path)?)
+}
+
+fn run(command: Command) -> Result<(), SudokuError> {
+    match command {
+        Command::Keygen { k, out } => {
+            let params: ParamsKZG<Bn256> = match k {
+                Some(k) => {
+                    let min_k = SudokuCircuit::<Fr>::default().min_k()?;
+                    if k < min_k {
+                        return Err(SudokuError::TooFewRows { k, min_k });
+                    }
+                    ParamsKZG::setup_params(k, OsRng)
+                }
+                None => setup(OsRng)?,
+            };
+            let (_, vk) = keygen(&params)?;
+
+            fs::create_dir_all(&out)?;
+            keys::save_params(&params, out.join(PARAMS_FILE))?;
+            keys::save_vk::<SudokuCircuit<Fr>>(&vk, &params, out.join(VK_FILE))?;
+        }
+        Command::Prove {
+            puzzle,
+            solution,
+            vk,
+            params,
+            solver_id,
+            out,
+        } => {
+            let puzzle = read_puzzle(&puzzle)?;
+            let mut circuit = match solution {
+                Some(solution) => SudokuCircuit::new(puzzle, read_solution(&solution)?)?,
+                None => SudokuCircuit::from_puzzle_solving(puzzle)?,
+            };
+            if let Some(solver_id) = solver_id {
+                circuit = circuit.with_solver_id(solver_id);
+            }
+
+            let params = load_params(params, &vk)?;
+            let pk = keys::load_pk::<SudokuCircuit<Fr>>(&vk, &params)?;
+            let proof = prove(&params, &pk, circuit, OsRng)?;
+            fs::write(out, proof)?;
+        }
+        Command::Verify {
+            puzzle,
+            vk,
+            params,
+            solver_id,
+            proof,
+        } => {
+            let puzzle = read_puzzle(&puzzle)?;
+
+            let params = load_params(params, &vk)?;
+            let vk = keys::load_vk::<SudokuCircuit<Fr>>(&vk, &params)?;
+            let proof = fs::read(proof)?;
+            verify(&params, &vk, &puzzle, solver_id, &proof)?;
+        }
+        Command::Stats { regions } => {
+            let stats = SudokuCircuit::<Fr>::default().stats()?;
+            println!("{}", stats);
+            if regions {
+                println!("rows by region:");
+                for region in &stats.regions {
+                    println!("  {:<30}{:>6}", region.name, region.rows);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli.command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::from(match err {
+                SudokuError::Verify(_) => EXIT_INVALID_PROOF,
+                SudokuError::Plonk(_) => EXIT_PROVER_FAILED,
+                _ => EXIT_BAD_INPUT,
+            })
+        }
+    }
+}
@@ -0,0 +1,461 @@
+//! Typed sudoku boards.
+//!
+//! [`Puzzle`] and [`Solution`] wrap an NxN grid of cells that has been checked to have the
+//! right shape and to only hold values the circuit accepts. The circuit checks the same things
+//! in-circuit, so these checks only serve to reject bad inputs early and with a useful error,
+//! rather than with a panic during synthesis or a proof that fails to verify.
+//!
+//! Boards are read and written in the common one-line format: the N*N cells in row-major order,
+//! one character each, with `0` or `.` for blank cells. Cells above 9 are written as letters
+//! (`A` for 10, `B` for 11 and so on), so boards of up to 35x35 cells can be written.
+//! [`PuzzleReader`] reads files holding one puzzle per line.
+
+use ndarray::Array2;
+use std::{
+    fmt,
+    io::{self, BufRead, Lines},
+    str::FromStr,
+};
+
+/// The reasons a board can be rejected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BoardError {
+    /// The board is not NxN
+    WrongShape { expected: usize, found: Vec<usize> },
+    /// A cell holds a value outside of the range allowed for the board
+    CellOutOfRange { row: usize, col: usize, value: u8 },
+    /// A solution cell disagrees with the clue given by the puzzle
+    Mismatch {
+        row: usize,
+        col: usize,
+        puzzle: u8,
+        solution: u8,
+    },
+    /// A one-line board doesn't have one character per cell
+    WrongLength { expected: usize, found: usize },
+    /// A one-line board holds a character that isn't a cell value
+    InvalidCharacter { index: usize, character: char },
+}
+
+impl fmt::Display for BoardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardError::WrongShape { expected, found } => write!(
+                f,
+                "expected a {}x{} board, got shape {:?}",
+                expected, expected, found
+            ),
+            BoardError::CellOutOfRange { row, col, value } => write!(
+                f,
+                "cell ({}, {}) holds {}, which is out of range",
+                row, col, value
+            ),
+            BoardError::Mismatch {
+                row,
+                col,
+                puzzle,
+                solution,
+            } => write!(
+                f,
+                "cell ({}, {}) of the solution holds {}, but the puzzle gives {}",
+                row, col, solution, puzzle
+            ),
+            BoardError::WrongLength { expected, found } => {
+                write!(f, "expected {} cells, got {} characters", expected, found)
+            }
+            BoardError::InvalidCharacter { index, character } => write!(
+                f,
+                "character {} is {:?}, which is not a cell value",
+                index, character
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BoardError {}
+
+// check_cells checks that `cells` is an NxN board holding values in [min_value, N]
+fn check_cells<const N: usize>(cells: &Array2<u8>, min_value: u8) -> Result<(), BoardError> {
+    if cells.shape() != [N, N] {
+        return Err(BoardError::WrongShape {
+            expected: N,
+            found: cells.shape().to_vec(),
+        });
+    }
+
+    for ((row, col), &value) in cells.indexed_iter() {
+        if value < min_value || usize::from(value) > N {
+            return Err(BoardError::CellOutOfRange { row, col, value });
+        }
+    }
+
+    Ok(())
+}
+
+// parse_cells reads an NxN board from the one-line format. Value ranges are left to the
+// constructors
+fn parse_cells<const N: usize>(line: &str) -> Result<Array2<u8>, BoardError> {
+    let cells = line
+        .chars()
+        .enumerate()
+        .map(|(index, character)| match character {
+            '.' => Ok(0),
+            _ => character
+                .to_digit(36)
+                .map(|value| value as u8)
+                .ok_or(BoardError::InvalidCharacter { index, character }),
+        })
+        .collect::<Result<Vec<u8>, BoardError>>()?;
+
+    if cells.len() != N * N {
+        return Err(BoardError::WrongLength {
+            expected: N * N,
+            found: cells.len(),
+        });
+    }
+
+    Ok(Array2::from_shape_vec((N, N), cells).expect("there are N*N cells"))
+}
+
+// fmt_cells writes a board in the one-line format, with `0` for blank cells
+fn fmt_cells(cells: &Array2<u8>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for &value in cells.iter() {
+        let character = char::from_digit(u32::from(value), 36).ok_or(fmt::Error)?;
+        write!(f, "{}", character.to_ascii_uppercase())?;
+    }
+
+    Ok(())
+}
+
+/// An NxN sudoku puzzle: each cell holds a clue in [1, N], or zero if it is blank.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Puzzle<const N: usize = 9>(Array2<u8>);
+
+impl<const N: usize> Puzzle<N> {
+    pub fn new(cells: Array2<u8>) -> Result<Self, BoardError> {
+        check_cells::<N>(&cells, 0)?;
+        Ok(Puzzle(cells))
+    }
+
+    // new_unchecked skips validation. A dishonest prover isn't bound by our constructors,
+    // so the circuit's own tests need to feed it boards these would reject
+    pub(crate) fn new_unchecked(cells: Array2<u8>) -> Self {
+        Puzzle(cells)
+    }
+
+    /// Returns a puzzle with every cell blank.
+    pub fn blank() -> Self {
+        Puzzle(Array2::zeros((N, N)))
+    }
+
+    pub fn cells(&self) -> &Array2<u8> {
+        &self.0
+    }
+}
+
+impl<const N: usize> FromStr for Puzzle<N> {
+    type Err = BoardError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        Puzzle::new(parse_cells::<N>(line)?)
+    }
+}
+
+impl<const N: usize> fmt::Display for Puzzle<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_cells(&self.0, f)
+    }
+}
+
+/// An NxN sudoku solution: each cell holds a digit in [1, N].
+///
+/// Only the range of each cell is checked here; whether the digits form a valid solution is
+/// left to the circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solution<const N: usize = 9>(Array2<u8>);
+
+impl<const N: usize> Solution<N> {
+    pub fn new(cells: Array2<u8>) -> Result<Self, BoardError> {
+        check_cells::<N>(&cells, 1)?;
+        Ok(Solution(cells))
+    }
+
+    // see `Puzzle::new_unchecked`
+    pub(crate) fn new_unchecked(cells: Array2<u8>) -> Self {
+        Solution(cells)
+    }
+
+    pub fn cells(&self) -> &Array2<u8> {
+        &self.0
+    }
+
+    /// Checks that this solution agrees with every clue of `puzzle`.
+    pub fn check_matches(&self, puzzle: &Puzzle<N>) -> Result<(), BoardError> {
+        for (((row, col), &digit), &clue) in self.0.indexed_iter().zip(puzzle.0.iter()) {
+            if clue != 0 && clue != digit {
+                return Err(BoardError::Mismatch {
+                    row,
+                    col,
+                    puzzle: clue,
+                    solution: digit,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<const N: usize> FromStr for Solution<N> {
+    type Err = BoardError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        Solution::new(parse_cells::<N>(line)?)
+    }
+}
+
+impl<const N: usize> fmt::Display for Solution<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_cells(&self.0, f)
+    }
+}
+
+/// The reasons reading a file of puzzles can fail.
+#[derive(Debug)]
+pub enum ReadPuzzleError {
+    Io(io::Error),
+    /// The puzzle on the given (1-based) line could not be parsed
+    Parse {
+        line: usize,
+        error: BoardError,
+    },
+    /// A puzzle was expected, but the file only holds blank lines and comments
+    NoPuzzle,
+}
+
+impl fmt::Display for ReadPuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadPuzzleError::Io(err) => write!(f, "{}", err),
+            ReadPuzzleError::Parse { line, error } => write!(f, "line {}: {}", line, error),
+            ReadPuzzleError::NoPuzzle => write!(f, "no puzzle found"),
+        }
+    }
+}
+
+impl std::error::Error for ReadPuzzleError {}
+
+/// Reads puzzles in the one-line format from a file holding one puzzle per line, one line
+/// at a time. Everything after a `#` is a comment, and blank lines are skipped.
+pub struct PuzzleReader<R, const N: usize = 9> {
+    lines: Lines<R>,
+    line: usize,
+}
+
+impl<R: BufRead, const N: usize> PuzzleReader<R, N> {
+    pub fn new(reader: R) -> Self {
+        PuzzleReader {
+            lines: reader.lines(),
+            line: 0,
+        }
+    }
+}
+
+impl<R: BufRead, const N: usize> Iterator for PuzzleReader<R, N> {
+    type Item = Result<Puzzle<N>, ReadPuzzleError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(err) => return Some(Err(ReadPuzzleError::Io(err))),
+            };
+            self.line += 1;
+
+            let content = line
+                .split_once('#')
+                .map_or(line.as_str(), |(content, _)| content);
+            let content = content.trim();
+            if !content.is_empty() {
+                return Some(content.parse().map_err(|error| ReadPuzzleError::Parse {
+                    line: self.line,
+                    error,
+                }));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn test_puzzle_validation() {
+        let puzzle = array![[1, 0, 0, 4], [0, 4, 1, 0], [2, 0, 0, 3], [0, 3, 2, 0]];
+        assert!(Puzzle::<4>::new(puzzle.clone()).is_ok());
+
+        assert_eq!(
+            Puzzle::<9>::new(puzzle.clone()),
+            Err(BoardError::WrongShape {
+                expected: 9,
+                found: vec![4, 4]
+            })
+        );
+
+        let mut out_of_range = puzzle;
+        out_of_range[[2, 1]] = 5;
+        assert_eq!(
+            Puzzle::<4>::new(out_of_range),
+            Err(BoardError::CellOutOfRange {
+                row: 2,
+                col: 1,
+                value: 5
+            })
+        );
+    }
+
+    #[test]
+    fn test_solution_validation() {
+        let solution = array![[1, 2, 3, 4], [3, 4, 1, 2], [2, 1, 4, 3], [4, 3, 2, 1]];
+        assert!(Solution::<4>::new(solution.clone()).is_ok());
+
+        assert!(matches!(
+            Solution::<4>::new(Array2::zeros((4, 3))),
+            Err(BoardError::WrongShape { .. })
+        ));
+
+        // blanks are only allowed in puzzles
+        let mut blank_cell = solution;
+        blank_cell[[3, 3]] = 0;
+        assert_eq!(
+            Solution::<4>::new(blank_cell),
+            Err(BoardError::CellOutOfRange {
+                row: 3,
+                col: 3,
+                value: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_solution_matches_puzzle() {
+        let puzzle = Puzzle::<4>::new(array![
+            [1, 0, 0, 4],
+            [0, 4, 1, 0],
+            [2, 0, 0, 3],
+            [0, 3, 2, 0]
+        ])
+        .unwrap();
+        let solution = Solution::<4>::new(array![
+            [1, 2, 3, 4],
+            [3, 4, 1, 2],
+            [2, 1, 4, 3],
+            [4, 3, 2, 1]
+        ])
+        .unwrap();
+        assert_eq!(solution.check_matches(&puzzle), Ok(()));
+        assert_eq!(solution.check_matches(&Puzzle::blank()), Ok(()));
+
+        let other_solution = Solution::<4>::new(array![
+            [4, 3, 2, 1],
+            [2, 1, 4, 3],
+            [3, 4, 1, 2],
+            [1, 2, 3, 4]
+        ])
+        .unwrap();
+        assert_eq!(
+            other_solution.check_matches(&puzzle),
+            Err(BoardError::Mismatch {
+                row: 0,
+                col: 0,
+                puzzle: 1,
+                solution: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_boards() {
+        let puzzle: Puzzle<4> = "1..4.41.2..3.32.".parse().unwrap();
+        assert_eq!(
+            puzzle,
+            Puzzle::new(array![
+                [1, 0, 0, 4],
+                [0, 4, 1, 0],
+                [2, 0, 0, 3],
+                [0, 3, 2, 0]
+            ])
+            .unwrap()
+        );
+        assert_eq!(puzzle.to_string(), "1004041020030320");
+        assert_eq!(puzzle.to_string().parse(), Ok(puzzle));
+
+        let solution: Solution<4> = "1234341221434321".parse().unwrap();
+        assert_eq!(solution.to_string(), "1234341221434321");
+
+        // cells above 9 are letters
+        let solution = Solution::<16>::new(Array2::from_shape_fn((16, 16), |(row, col)| {
+            ((row * 4 + row / 4 + col) % 16 + 1) as u8
+        }))
+        .unwrap();
+        assert!(solution.to_string().starts_with("123456789ABCDEFG"));
+        assert_eq!(solution.to_string().parse(), Ok(solution));
+    }
+
+    #[test]
+    fn test_parse_invalid_boards() {
+        assert_eq!(
+            "1..4.41.2..3.32".parse::<Puzzle<4>>(),
+            Err(BoardError::WrongLength {
+                expected: 16,
+                found: 15
+            })
+        );
+        assert_eq!(
+            "1..4.41.2..3.3x.".parse::<Puzzle<4>>(),
+            Err(BoardError::InvalidCharacter {
+                index: 14,
+                character: 'x'
+            })
+        );
+        assert_eq!(
+            "1..5.41.2..3.32.".parse::<Puzzle<4>>(),
+            Err(BoardError::CellOutOfRange {
+                row: 0,
+                col: 3,
+                value: 5
+            })
+        );
+
+        // solutions can't have blanks
+        assert!(matches!(
+            "1234341221434.21".parse::<Solution<4>>(),
+            Err(BoardError::CellOutOfRange { value: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_puzzle_reader() {
+        let file = "# a small corpus\n\
+                    1..4.41.2..3.32.\n\
+                    \n\
+                    0000000000000000  # blank\n\
+                    1..4.41.2..3.32\n";
+        let mut reader = PuzzleReader::<_, 4>::new(file.as_bytes());
+        assert_eq!(
+            reader.next().unwrap().unwrap().to_string(),
+            "1004041020030320"
+        );
+        assert_eq!(reader.next().unwrap().unwrap(), Puzzle::blank());
+        assert!(matches!(
+            reader.next(),
+            Some(Err(ReadPuzzleError::Parse {
+                line: 5,
+                error: BoardError::WrongLength { .. }
+            }))
+        ));
+        assert!(reader.next().is_none());
+    }
+}
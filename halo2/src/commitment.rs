@@ -0,0 +1,90 @@
+//! How the puzzle is exposed to the verifier.
+//!
+//! By default every puzzle cell is its own public input, which is simple but makes each proof
+//! of an NxN board carry N² public inputs (81 for 9x9). Verifying those on-chain means hashing
+//! and storing all of them, so the circuit can instead hash the puzzle with Poseidon in-circuit
+//! and expose only the digest. Verifiers then compute the same digest natively with
+//! [`hash_puzzle`].
+
+use crate::Puzzle;
+use halo2wrong::halo2::arithmetic::FieldExt;
+use poseidon::{Poseidon, Spec};
+use std::fmt::Debug;
+
+/// Width of the Poseidon permutation's state
+pub const POSEIDON_T: usize = 5;
+
+/// Number of field elements absorbed per permutation
+pub const POSEIDON_RATE: usize = 4;
+
+/// Number of full rounds of the Poseidon permutation
+pub const POSEIDON_R_F: usize = 8;
+
+/// Number of partial rounds of the Poseidon permutation
+pub const POSEIDON_R_P: usize = 60;
+
+/// The two ways of exposing the puzzle as a public input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PuzzleCommitmentMode {
+    /// Each of the N² puzzle cells is a public input of its own.
+    Cells,
+    /// The only public input for the puzzle is the Poseidon hash of its cells (see
+    /// [`hash_puzzle`]). Absorbing the N² cells, plus padding, costs the rows of
+    /// `N² / POSEIDON_RATE + 1` Poseidon permutations in-circuit (21 for 9x9 boards).
+    PoseidonHash,
+}
+
+/// Type-level choice of a [`PuzzleCommitmentMode`], so that `Circuit::configure` knows which
+/// commitment to build.
+pub trait PuzzleCommitmentKind: Clone + Debug + Default {
+    const MODE: PuzzleCommitmentMode;
+}
+
+/// Selects [`PuzzleCommitmentMode::Cells`]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PuzzleCells;
+
+impl PuzzleCommitmentKind for PuzzleCells {
+    const MODE: PuzzleCommitmentMode = PuzzleCommitmentMode::Cells;
+}
+
+/// Selects [`PuzzleCommitmentMode::PoseidonHash`]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PoseidonPuzzleHash;
+
+impl PuzzleCommitmentKind for PoseidonPuzzleHash {
+    const MODE: PuzzleCommitmentMode = PuzzleCommitmentMode::PoseidonHash;
+}
+
+// poseidon_spec returns the round constants and MDS matrix shared by the in-circuit and the
+// native hasher. The two must agree exactly for the digests to match
+pub(crate) fn poseidon_spec<F: FieldExt>() -> Spec<F, POSEIDON_T, POSEIDON_RATE> {
+    Spec::new(POSEIDON_R_F, POSEIDON_R_P)
+}
+
+/// Computes the digest the circuit exposes in [`PuzzleCommitmentMode::PoseidonHash`]: the
+/// Poseidon hash of the puzzle cells in row-major order.
+pub fn hash_puzzle<F: FieldExt, const N: usize>(puzzle: &Puzzle<N>) -> F {
+    let mut hasher = Poseidon::<F, POSEIDON_T, POSEIDON_RATE>::new(POSEIDON_R_F, POSEIDON_R_P);
+    let cells: Vec<F> = puzzle
+        .cells()
+        .iter()
+        .map(|&value| F::from(u64::from(value)))
+        .collect();
+    hasher.update(&cells);
+    hasher.squeeze()
+}
+
+/// Arranges the public inputs committing to `puzzle` in the given mode.
+pub fn puzzle_public_inputs<F: FieldExt, const N: usize>(
+    puzzle: &Puzzle<N>,
+    mode: PuzzleCommitmentMode,
+) -> Vec<F> {
+    match mode {
+        PuzzleCommitmentMode::Cells => puzzle
+            .cells()
+            .mapv(|value| F::from_u128(u128::from(value)))
+            .into_raw_vec(),
+        PuzzleCommitmentMode::PoseidonHash => vec![hash_puzzle(puzzle)],
+    }
+}
@@ -0,0 +1,490 @@
+//! Explains why a circuit fails to verify, in terms of the board rather than of gates and
+//! rows.
+//!
+//! Synthesis names every region after the part of the board it checks: "row 3", "column 3"
+//! and "box (1,2)" for the group checks, and "cell (0,0)" for the check that a cell of the
+//! solution agrees with the puzzle's clue. [`diagnose`] runs a circuit through the mock
+//! prover, traces each failure back to its region, and looks at the boards to say what is
+//! wrong there, e.g. [`SudokuViolation::DuplicateInRow`].
+
+use crate::{
+    commitment::PuzzleCommitmentKind, group::GroupCheckStrategy, isqrt,
+    public_inputs_with_commitment, range::RangeCheckKind, SudokuCircuit, LOAD_BOARDS_REGION,
+};
+use halo2wrong::halo2::{
+    arithmetic::FieldExt,
+    dev::{metadata, FailureLocation, MockProver, VerifyFailure},
+    plonk::Error,
+};
+use std::fmt;
+
+/// A row, column or box of the board. Rows and columns are numbered from 0, top to bottom
+/// and left to right, and boxes by their row and column among the boxes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Group {
+    Row(usize),
+    Column(usize),
+    Box(usize, usize),
+}
+
+impl Group {
+    /// Returns every group of an `N` by `N` board, in the order of [`SudokuCircuit::groups`].
+    pub fn all<const N: usize>() -> Vec<Group> {
+        let box_size = isqrt(N);
+        let rows = (0..N).map(Group::Row);
+        let cols = (0..N).map(Group::Column);
+        let boxes = (0..N).map(|i| Group::Box(i / box_size, i % box_size));
+
+        rows.chain(cols).chain(boxes).collect()
+    }
+
+    /// Returns the (row, column) of each cell in the group, in the order they are checked.
+    pub fn cells<const N: usize>(&self) -> Vec<(usize, usize)> {
+        let box_size = isqrt(N);
+        match *self {
+            Group::Row(row) => (0..N).map(|col| (row, col)).collect(),
+            Group::Column(col) => (0..N).map(|row| (row, col)).collect(),
+            Group::Box(box_row, box_col) => (0..N)
+                .map(|i| {
+                    (
+                        box_row * box_size + i / box_size,
+                        box_col * box_size + i % box_size,
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for Group {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Group::Row(row) => write!(f, "row {}", row),
+            Group::Column(col) => write!(f, "column {}", col),
+            Group::Box(box_row, box_col) => write!(f, "box ({},{})", box_row, box_col),
+        }
+    }
+}
+
+// cell_region_name names the region checking the cell at (`row`, `col`) against its clue
+pub(crate) fn cell_region_name(row: usize, col: usize) -> String {
+    format!("cell ({},{})", row, col)
+}
+
+// from_cell_region_name recovers the cell a region was named after
+pub(crate) fn from_cell_region_name(name: &str) -> Option<(usize, usize)> {
+    parse_position(name.strip_prefix("cell ")?)
+}
+
+// parse_position parses "(a,b)"
+fn parse_position(position: &str) -> Option<(usize, usize)> {
+    let (a, b) = position
+        .strip_prefix('(')?
+        .strip_suffix(')')?
+        .split_once(',')?;
+    Some((a.parse().ok()?, b.parse().ok()?))
+}
+
+// group_of recovers the group a region was named after, if any
+pub(crate) fn group_of(region_name: &str) -> Option<Group> {
+    if let Some(row) = region_name.strip_prefix("row ") {
+        return row.parse().ok().map(Group::Row);
+    }
+    if let Some(col) = region_name.strip_prefix("column ") {
+        return col.parse().ok().map(Group::Column);
+    }
+    let (box_row, box_col) = parse_position(region_name.strip_prefix("box ")?)?;
+    Some(Group::Box(box_row, box_col))
+}
+
+/// A reason a solution fails to prove knowledge of a solution to the puzzle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SudokuViolation {
+    /// A cell of the solution isn't a digit 1..=N
+    CellOutOfRange {
+        row: usize,
+        col: usize,
+        value: u8,
+    },
+    /// A clue of the puzzle is neither blank nor a digit 1..=N
+    ClueOutOfRange {
+        row: usize,
+        col: usize,
+        value: u8,
+    },
+    /// A cell of the solution differs from the puzzle's clue for it
+    MismatchesClue {
+        row: usize,
+        col: usize,
+        clue: u8,
+        value: u8,
+    },
+    DuplicateInRow {
+        row: usize,
+        value: u8,
+    },
+    DuplicateInColumn {
+        col: usize,
+        value: u8,
+    },
+    DuplicateInBox {
+        box_row: usize,
+        box_col: usize,
+        value: u8,
+    },
+    /// The public inputs aren't the puzzle and solver id the circuit was built with
+    PublicInputMismatch,
+    /// A failure that couldn't be traced back to the board, as reported by the mock prover
+    Other(String),
+}
+
+impl fmt::Display for SudokuViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SudokuViolation::CellOutOfRange { row, col, value } => {
+                write!(
+                    f,
+                    "cell ({},{}) holds {}, which is not a digit",
+                    row, col, value
+                )
+            }
+            SudokuViolation::ClueOutOfRange { row, col, value } => {
+                write!(
+                    f,
+                    "clue ({},{}) is {}, which is not a digit",
+                    row, col, value
+                )
+            }
+            SudokuViolation::MismatchesClue {
+                row,
+                col,
+                clue,
+                value,
+            } => write!(
+                f,
+                "cell ({},{}) mismatches given clue: {} instead of {}",
+                row, col, value, clue
+            ),
+            SudokuViolation::DuplicateInRow { row, value } => {
+                write!(f, "{} holds {} more than once", Group::Row(*row), value)
+            }
+            SudokuViolation::DuplicateInColumn { col, value } => {
+                write!(f, "{} holds {} more than once", Group::Column(*col), value)
+            }
+            SudokuViolation::DuplicateInBox {
+                box_row,
+                box_col,
+                value,
+            } => write!(
+                f,
+                "{} holds {} more than once",
+                Group::Box(*box_row, *box_col),
+                value
+            ),
+            SudokuViolation::PublicInputMismatch => {
+                write!(
+                    f,
+                    "the public inputs don't match the circuit's puzzle and solver"
+                )
+            }
+            SudokuViolation::Other(failure) => write!(f, "{}", failure),
+        }
+    }
+}
+
+/// Runs `circuit` through the mock prover against `instances`, and explains every failure in
+/// terms of the board. Returns no violations if the circuit verifies.
+pub fn diagnose<F, const N: usize, R, C, G>(
+    circuit: &SudokuCircuit<F, N, R, C, G>,
+    instances: Vec<Vec<F>>,
+) -> Result<Vec<SudokuViolation>, Error>
+where
+    F: FieldExt,
+    R: RangeCheckKind,
+    C: PuzzleCommitmentKind,
+    G: GroupCheckStrategy,
+{
+    let stats = circuit.stats()?;
+    let expected_instances =
+        public_inputs_with_commitment(circuit.puzzle(), circuit.solver_id(), C::MODE);
+    let public_inputs_match = instances == expected_instances;
+
+    let failures = match MockProver::run(stats.min_k, circuit, instances)?.verify() {
+        Ok(()) => return Ok(vec![]),
+        Err(failures) => failures,
+    };
+
+    let mut violations = vec![];
+    if !public_inputs_match {
+        violations.push(SudokuViolation::PublicInputMismatch);
+    }
+    let region_names: Vec<String> = stats
+        .regions
+        .into_iter()
+        .map(|region| region.name)
+        .collect();
+    for failure in &failures {
+        for violation in explain(circuit, &region_names, failure) {
+            if !violations.contains(&violation) {
+                violations.push(violation);
+            }
+        }
+    }
+
+    // never report a failing circuit as sound
+    if violations.is_empty() {
+        violations.extend(
+            failures
+                .iter()
+                .map(|failure| SudokuViolation::Other(failure.to_string())),
+        );
+    }
+    Ok(violations)
+}
+
+// explain traces `failure` back to the region it happened in, and looks at the part of the
+// board that region checks. Copy constraint failures are only explained when they happen
+// in a group check: elsewhere they stem from the public inputs, which `diagnose` compares
+// natively, and a group's failing copy can drag in cells of groups that are fine
+fn explain<F, const N: usize, R, C, G>(
+    circuit: &SudokuCircuit<F, N, R, C, G>,
+    region_names: &[String],
+    failure: &VerifyFailure,
+) -> Vec<SudokuViolation>
+where
+    F: FieldExt,
+    R: RangeCheckKind,
+    C: PuzzleCommitmentKind,
+    G: GroupCheckStrategy,
+{
+    let other = || vec![SudokuViolation::Other(failure.to_string())];
+    let (region, offset, is_copy) = match failure {
+        VerifyFailure::ConstraintNotSatisfied {
+            location: FailureLocation::InRegion { region, offset },
+            ..
+        }
+        | VerifyFailure::Lookup {
+            location: FailureLocation::InRegion { region, offset },
+            ..
+        } => (region, *offset, false),
+        VerifyFailure::Permutation {
+            location: FailureLocation::InRegion { region, offset },
+            ..
+        } => (region, *offset, true),
+        VerifyFailure::Permutation { .. } => return vec![],
+        _ => return other(),
+    };
+    let name = match region_names
+        .iter()
+        .enumerate()
+        .find(|(index, name)| *region == metadata::Region::from((*index, name.as_str())))
+    {
+        Some((_, name)) => name.as_str(),
+        None => return other(),
+    };
+
+    let puzzle = circuit.puzzle().cells();
+    let solution = circuit.solution().cells();
+    let is_digit = |value: u8| (1..=N).contains(&usize::from(value));
+
+    if let Some(group) = group_of(name) {
+        let values: Vec<u8> = group
+            .cells::<N>()
+            .into_iter()
+            .map(|(row, col)| solution[[row, col]])
+            .collect();
+        let mut duplicates: Vec<u8> = values
+            .iter()
+            .copied()
+            .filter(|&value| is_digit(value) && values.iter().filter(|&&v| v == value).count() > 1)
+            .collect();
+        duplicates.sort_unstable();
+        duplicates.dedup();
+
+        let violations: Vec<SudokuViolation> = duplicates
+            .into_iter()
+            .map(|value| match group {
+                Group::Row(row) => SudokuViolation::DuplicateInRow { row, value },
+                Group::Column(col) => SudokuViolation::DuplicateInColumn { col, value },
+                Group::Box(box_row, box_col) => SudokuViolation::DuplicateInBox {
+                    box_row,
+                    box_col,
+                    value,
+                },
+            })
+            .collect();
+        // a cell that isn't a digit fails its group too, but is already reported by the
+        // range check
+        if violations.is_empty() && !is_copy && values.iter().all(|&value| is_digit(value)) {
+            return other();
+        }
+        return violations;
+    }
+    if is_copy {
+        return vec![];
+    }
+
+    if let Some((row, col)) = from_cell_region_name(name) {
+        let (clue, value) = (puzzle[[row, col]], solution[[row, col]]);
+        if clue != 0 && clue != value {
+            return vec![SudokuViolation::MismatchesClue {
+                row,
+                col,
+                clue,
+                value,
+            }];
+        }
+        return other();
+    }
+
+    // the boards are loaded one cell per row, the puzzle's cells first
+    if name == LOAD_BOARDS_REGION && offset < 2 * N * N {
+        let (row, col) = (offset % (N * N) / N, offset % N);
+        if offset < N * N {
+            let value = puzzle[[row, col]];
+            if value != 0 && !is_digit(value) {
+                return vec![SudokuViolation::ClueOutOfRange { row, col, value }];
+            }
+        } else {
+            let value = solution[[row, col]];
+            if !is_digit(value) {
+                return vec![SudokuViolation::CellOutOfRange { row, col, value }];
+            }
+        }
+    }
+    other()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commitment::PuzzleCells,
+        fixtures,
+        group::{Fingerprint, IndexHint, MultisetProduct, PairwiseDistinct, Shuffle},
+        public_inputs,
+        range::{LookupRangeCheck, PolynomialRangeCheck},
+        Puzzle, Solution,
+    };
+    use halo2wrong::halo2::halo2curves::bn256::Fr as Fp;
+    use ndarray::Array2;
+
+    // circuit builds a circuit around the standard puzzle and solution, after letting
+    // `tamper` break them
+    fn circuit<R: RangeCheckKind, G: GroupCheckStrategy>(
+        tamper: impl FnOnce(&mut Array2<u8>, &mut Array2<u8>),
+    ) -> SudokuCircuit<Fp, 9, R, PuzzleCells, G> {
+        let mut puzzle = fixtures::puzzle_cells();
+        let mut solution = fixtures::solution_cells();
+        tamper(&mut puzzle, &mut solution);
+
+        SudokuCircuit::new_unchecked(
+            Puzzle::new_unchecked(puzzle),
+            Solution::new_unchecked(solution),
+        )
+    }
+
+    fn check_duplicates<G: GroupCheckStrategy>() {
+        // the 4 at the top left becomes a second 3 in its row, column and box
+        let circuit = circuit::<PolynomialRangeCheck, G>(|_, solution| solution[[0, 0]] = 3);
+        let violations = circuit.diagnose().unwrap();
+        assert_eq!(violations.len(), 3, "{:?}", violations);
+        assert!(violations.contains(&SudokuViolation::DuplicateInRow { row: 0, value: 3 }));
+        assert!(violations.contains(&SudokuViolation::DuplicateInColumn { col: 0, value: 3 }));
+        assert!(violations.contains(&SudokuViolation::DuplicateInBox {
+            box_row: 0,
+            box_col: 0,
+            value: 3
+        }));
+    }
+
+    #[test]
+    fn test_valid_solution() {
+        let circuit = circuit::<PolynomialRangeCheck, PairwiseDistinct>(|_, _| {});
+        assert_eq!(circuit.diagnose().unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_duplicates() {
+        check_duplicates::<PairwiseDistinct>();
+        check_duplicates::<Fingerprint>();
+        check_duplicates::<Shuffle>();
+        check_duplicates::<IndexHint>();
+        check_duplicates::<MultisetProduct>();
+    }
+
+    #[test]
+    fn test_mismatched_clue() {
+        // the solution is valid, but for a puzzle with a 5 at the top left
+        let circuit =
+            circuit::<PolynomialRangeCheck, PairwiseDistinct>(|puzzle, _| puzzle[[0, 0]] = 5);
+        let violations = circuit.diagnose().unwrap();
+        assert_eq!(
+            violations,
+            vec![SudokuViolation::MismatchesClue {
+                row: 0,
+                col: 0,
+                clue: 5,
+                value: 4
+            }]
+        );
+        assert_eq!(
+            violations[0].to_string(),
+            "cell (0,0) mismatches given clue: 4 instead of 5"
+        );
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let expected = vec![SudokuViolation::CellOutOfRange {
+            row: 1,
+            col: 2,
+            value: 0,
+        }];
+        let circuit =
+            circuit::<PolynomialRangeCheck, PairwiseDistinct>(|_, solution| solution[[1, 2]] = 0);
+        assert_eq!(circuit.diagnose().unwrap(), expected);
+        let circuit =
+            circuit::<LookupRangeCheck, PairwiseDistinct>(|_, solution| solution[[1, 2]] = 0);
+        assert_eq!(circuit.diagnose().unwrap(), expected);
+
+        let circuit =
+            circuit::<PolynomialRangeCheck, PairwiseDistinct>(|puzzle, _| puzzle[[0, 0]] = 10);
+        assert!(circuit
+            .diagnose()
+            .unwrap()
+            .contains(&SudokuViolation::ClueOutOfRange {
+                row: 0,
+                col: 0,
+                value: 10
+            }));
+    }
+
+    #[test]
+    fn test_public_input_mismatch() {
+        let circuit = circuit::<PolynomialRangeCheck, PairwiseDistinct>(|_, _| {});
+        let mut other_puzzle = circuit.puzzle().cells().clone();
+        other_puzzle[[0, 0]] = 4;
+        let instances = public_inputs(&Puzzle::<9>::new_unchecked(other_puzzle), None);
+        assert_eq!(
+            diagnose(&circuit, instances).unwrap(),
+            vec![SudokuViolation::PublicInputMismatch]
+        );
+    }
+
+    #[test]
+    fn test_region_names() {
+        assert_eq!(Group::all::<9>().len(), 27);
+        for group in Group::all::<9>() {
+            assert_eq!(group_of(&group.to_string()), Some(group));
+        }
+        assert_eq!(Group::Box(1, 2).to_string(), "box (1,2)");
+        assert_eq!(
+            Group::Box(1, 1).cells::<4>(),
+            vec![(2, 2), (2, 3), (3, 2), (3, 3)]
+        );
+        assert_eq!(from_cell_region_name(&cell_region_name(0, 8)), Some((0, 8)));
+        assert_eq!(group_of("load boards"), None);
+        assert_eq!(group_of("row x"), None);
+    }
+}
@@ -0,0 +1,126 @@
+//! The crate-level error type.
+//!
+//! Each module reports failures with an error of its own ([`BoardError`],
+//! [`ReadPuzzleError`], [`InputError`], [`SolveError`], [`KeyFileError`], [`VerifyError`], or
+//! halo2's [`plonk::Error`] during synthesis and proving). [`SudokuError`] wraps all of them,
+//! so that code driving the whole pipeline, from reading boards and keys to verifying a proof,
+//! can use `?` throughout.
+
+use crate::{
+    board::ReadPuzzleError, input::InputError, keys::KeyFileError, BoardError, SolveError,
+    VerifyError,
+};
+use halo2wrong::halo2::plonk;
+use std::{fmt, io};
+
+/// Any error raised by this crate.
+#[derive(Debug)]
+pub enum SudokuError {
+    /// A puzzle or solution was rejected before building the circuit
+    Board(BoardError),
+    /// A file of puzzles could not be read
+    ReadPuzzle(ReadPuzzleError),
+    /// A JSON input file could not be loaded
+    Input(InputError),
+    /// A puzzle doesn't have exactly one solution
+    Solve(SolveError),
+    /// Synthesis, key generation or proving failed
+    Plonk(plonk::Error),
+    /// A proof failed to verify
+    Verify(VerifyError),
+    /// A parameters or key file could not be loaded
+    KeyFile(KeyFileError),
+    /// Parameters were requested for circuits of `2^k` rows, fewer than the circuit needs
+    TooFewRows {
+        k: u32,
+        min_k: u32,
+    },
+    /// The EVM verifier contract could not be generated or compiled, e.g. with solc's errors
+    EvmVerifier(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for SudokuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SudokuError::Board(err) => write!(f, "invalid board: {}", err),
+            SudokuError::ReadPuzzle(err) => write!(f, "{}", err),
+            SudokuError::Input(err) => write!(f, "{}", err),
+            SudokuError::Solve(err) => write!(f, "{}", err),
+            SudokuError::Plonk(err) => write!(f, "{}", err),
+            SudokuError::Verify(err) => write!(f, "{}", err),
+            SudokuError::KeyFile(err) => write!(f, "{}", err),
+            SudokuError::TooFewRows { k, min_k } => write!(
+                f,
+                "k = {} is too small, the circuit needs k of at least {}",
+                k, min_k
+            ),
+            SudokuError::EvmVerifier(err) => write!(f, "{}", err),
+            SudokuError::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for SudokuError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SudokuError::Board(err) => Some(err),
+            SudokuError::ReadPuzzle(err) => Some(err),
+            SudokuError::Input(err) => Some(err),
+            SudokuError::Solve(err) => Some(err),
+            SudokuError::Plonk(err) => Some(err),
+            SudokuError::Verify(err) => Some(err),
+            SudokuError::KeyFile(err) => Some(err),
+            SudokuError::TooFewRows { .. } | SudokuError::EvmVerifier(_) => None,
+            SudokuError::Io(err) => Some(err),
+        }
+    }
+}
+
+impl From<BoardError> for SudokuError {
+    fn from(err: BoardError) -> Self {
+        SudokuError::Board(err)
+    }
+}
+
+impl From<ReadPuzzleError> for SudokuError {
+    fn from(err: ReadPuzzleError) -> Self {
+        SudokuError::ReadPuzzle(err)
+    }
+}
+
+impl From<InputError> for SudokuError {
+    fn from(err: InputError) -> Self {
+        SudokuError::Input(err)
+    }
+}
+
+impl From<SolveError> for SudokuError {
+    fn from(err: SolveError) -> Self {
+        SudokuError::Solve(err)
+    }
+}
+
+impl From<plonk::Error> for SudokuError {
+    fn from(err: plonk::Error) -> Self {
+        SudokuError::Plonk(err)
+    }
+}
+
+impl From<VerifyError> for SudokuError {
+    fn from(err: VerifyError) -> Self {
+        SudokuError::Verify(err)
+    }
+}
+
+impl From<KeyFileError> for SudokuError {
+    fn from(err: KeyFileError) -> Self {
+        SudokuError::KeyFile(err)
+    }
+}
+
+impl From<io::Error> for SudokuError {
+    fn from(err: io::Error) -> Self {
+        SudokuError::Io(err)
+    }
+}
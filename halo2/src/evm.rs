@@ -0,0 +1,315 @@
+//! On-chain verification of sudoku proofs.
+//!
+//! Generates a Solidity verifier contract for [`SudokuCircuit`]'s verifying key using
+//! `snark-verifier`. `snark-verifier` emits the verifier as Yul (the assembly language
+//! Solidity compiles through), which [`gen_evm_verifier_sol`] wraps in the fallback function
+//! of a Solidity contract. [`gen_evm_verifier`] compiles that contract to deployment bytecode
+//! with `solc`, which must be on the `PATH`, failing with [`SudokuError::Io`] if `solc` can't be
+//! run and [`SudokuError::EvmVerifier`] if it fails to compile the contract.
+//!
+//! The contract verifies KZG proofs over bn256 made with the GWC multiopen scheme and a
+//! Keccak transcript, so proofs meant for it must be made with [`prove_for_evm`] rather than
+//! [`crate::prove`]. Its calldata is the public inputs followed by the proof, see
+//! [`encode_calldata`].
+
+use crate::{public_inputs, Puzzle, SudokuCircuit, SudokuError};
+use halo2wrong::halo2::{
+    halo2curves::bn256::{Bn256, Fq, Fr, G1Affine},
+    plonk::{create_proof, Error, ProvingKey, VerifyingKey},
+    poly::kzg::{
+        commitment::{KZGCommitmentScheme, ParamsKZG},
+        multiopen::ProverGWC,
+    },
+    transcript::TranscriptWriterBuffer,
+};
+use rand_core::RngCore;
+use snark_verifier::{
+    loader::evm::{self, EvmLoader},
+    pcs::kzg::{Gwc19, Kzg},
+    system::halo2::{compile, transcript::evm::EvmTranscript, Config},
+    verifier::{self, PlonkVerifier},
+};
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    rc::Rc,
+};
+
+type Plonk = verifier::Plonk<Kzg<Bn256, Gwc19>>;
+
+// The number of public inputs in each of the circuit's instance columns
+fn num_instance() -> Vec<usize> {
+    public_inputs::<Fr, 9>(&Puzzle::blank(), None)
+        .iter()
+        .map(Vec::len)
+        .collect()
+}
+
+/// Generates the Yul source of a verifier contract for proofs made with `vk`.
+pub fn gen_evm_verifier_yul(params: &ParamsKZG<Bn256>, vk: &VerifyingKey<G1Affine>) -> String {
+    let num_instance = num_instance();
+    let svk = params.get_g()[0].into();
+    let dk = (params.g2(), params.s_g2()).into();
+    let protocol = compile(
+        params,
+        vk,
+        Config::kzg().with_num_instance(num_instance.clone()),
+    );
+
+    // Running the verifier against the EVM loader records every operation it performs as Yul,
+    // rather than carrying the operations out
+    let loader = EvmLoader::new::<Fq, Fr>();
+    let protocol = protocol.loaded(&loader);
+    let mut transcript = EvmTranscript::<_, Rc<EvmLoader>, _, _>::new(&loader);
+    let instances = transcript.load_instances(num_instance);
+    let proof = Plonk::read_proof(&svk, &protocol, &instances, &mut transcript);
+    Plonk::verify(&svk, &dk, &protocol, &instances, &proof);
+
+    loader.yul_code()
+}
+
+/// Generates the Solidity source of a verifier contract, `SudokuVerifier`, for proofs made
+/// with `vk`. The contract has no functions: its fallback reverts unless the calldata (see
+/// [`encode_calldata`]) holds a valid proof.
+pub fn gen_evm_verifier_sol(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+) -> Result<String, SudokuError> {
+    let yul = gen_evm_verifier_yul(params, vk);
+    let runtime = runtime_code(&yul)
+        .ok_or_else(|| {
+            SudokuError::EvmVerifier("snark-verifier's Yul has no runtime object".to_string())
+        })?
+        .trim();
+
+    // The runtime code never hands control back to Solidity, as it ends in a `return` or a
+    // `revert`, so it is free to use all of memory
+    let body: String = runtime
+        .lines()
+        .map(|line| format!("            {}\n", line.trim_end()))
+        .collect();
+    Ok(format!(
+        "// SPDX-License-Identifier: UNLICENSED
+pragma solidity ^0.8.0;
+
+// Verifies proofs of knowledge of a sudoku solution. Generated by sudoku_halo2::evm.
+contract SudokuVerifier {{
+    fallback() external {{
+        assembly {{
+{}        }}
+    }}
+}}
+",
+        body
+    ))
+}
+
+// runtime_code returns the body of the `code` block of the "Runtime" object of a Yul object,
+// which is the code the deployed contract runs
+fn runtime_code(yul: &str) -> Option<&str> {
+    let runtime = &yul[yul.find("object \"Runtime\"")?..];
+    let code = &runtime[runtime.find("code")?..];
+    let start = code.find('{')? + 1;
+
+    let mut depth = 1;
+    for (offset, c) in code[start..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&code[start..start + offset]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Generates the deployment bytecode of the Solidity verifier contract generated by
+/// [`gen_evm_verifier_sol`], compiling it with `solc`.
+pub fn gen_evm_verifier(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+) -> Result<Vec<u8>, SudokuError> {
+    compile_solidity(&gen_evm_verifier_sol(params, vk)?)
+}
+
+// compile_solidity compiles a single-contract Solidity source to deployment bytecode with
+// `solc`, as `snark_verifier::loader::evm::compile_yul` does for Yul
+fn compile_solidity(source: &str) -> Result<Vec<u8>, SudokuError> {
+    let mut solc = Command::new("solc")
+        .args(["--bin", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // stdin is dropped once written, closing it so that solc starts compiling
+    if let Some(mut stdin) = solc.stdin.take() {
+        stdin.write_all(source.as_bytes())?;
+    }
+    let output = solc.wait_with_output()?;
+    if !output.status.success() {
+        return Err(SudokuError::EvmVerifier(format!(
+            "solc failed to compile the verifier: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    // solc prints the bytecode as hex on the line after "Binary:"
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let bytecode = stdout
+        .lines()
+        .skip_while(|line| !line.starts_with("Binary:"))
+        .nth(1)
+        .map(str::trim)
+        .filter(|bytecode| bytecode.is_ascii() && bytecode.len() % 2 == 0)
+        .ok_or_else(|| SudokuError::EvmVerifier("solc didn't print the bytecode".to_string()))?;
+    (0..bytecode.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&bytecode[i..i + 2], 16))
+        .collect::<Result<_, _>>()
+        .map_err(|_| SudokuError::EvmVerifier("solc printed malformed bytecode".to_string()))
+}
+
+/// Proves knowledge of the solution held by `circuit` in the form expected by the contract
+/// generated by [`gen_evm_verifier`]. If the circuit has a solver id, the contract only accepts
+/// the proof for that solver.
+pub fn prove_for_evm(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: SudokuCircuit<Fr>,
+    rng: impl RngCore,
+) -> Result<Vec<u8>, Error> {
+    let instances: Vec<Vec<Fr>> = public_inputs(circuit.puzzle(), circuit.solver_id());
+    let instances: Vec<&[Fr]> = instances.iter().map(Vec::as_slice).collect();
+
+    let mut transcript = TranscriptWriterBuffer::<_, G1Affine, _>::init(vec![]);
+    create_proof::<
+        KZGCommitmentScheme<Bn256>,
+        ProverGWC<'_, Bn256>,
+        _,
+        _,
+        EvmTranscript<_, _, _, _>,
+        _,
+    >(
+        params,
+        pk,
+        &[circuit],
+        &[instances.as_slice()],
+        rng,
+        &mut transcript,
+    )?;
+
+    Ok(transcript.finalize())
+}
+
+/// Encodes the calldata for a call to the verifier contract checking `proof` against `puzzle`
+/// and `solver_id`.
+pub fn encode_calldata(puzzle: &Puzzle, solver_id: Option<Fr>, proof: &[u8]) -> Vec<u8> {
+    evm::encode_calldata(&public_inputs(puzzle, solver_id), proof)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{fixtures, keygen, CommitmentParams, K};
+    use rand::rngs::OsRng;
+    use revm::{
+        primitives::{Address, CreateScheme, ExecutionResult, Output, TransactTo},
+        InMemoryDB, EVM,
+    };
+
+    // Evm deploys a contract into a fresh in-process EVM and calls it
+    struct Evm {
+        evm: EVM<InMemoryDB>,
+    }
+
+    impl Evm {
+        fn new() -> Self {
+            let mut evm = EVM::new();
+            evm.database(InMemoryDB::default());
+            // The verifier is larger than mainnet's 24KB contract size limit, which only
+            // matters when deploying it to mainnet
+            evm.env.cfg.limit_contract_code_size = Some(usize::MAX);
+            evm.env.tx.gas_limit = u64::MAX;
+            Evm { evm }
+        }
+
+        fn deploy(&mut self, deployment_code: Vec<u8>) -> Address {
+            self.evm.env.tx.transact_to = TransactTo::Create(CreateScheme::Create);
+            self.evm.env.tx.data = deployment_code.into();
+            match self.evm.transact_commit().unwrap() {
+                ExecutionResult::Success {
+                    output: Output::Create(_, Some(address)),
+                    ..
+                } => address,
+                result => panic!("deployment failed: {:?}", result),
+            }
+        }
+
+        // call returns whether the call succeeded, i.e. whether the proof verified
+        fn call(&mut self, address: Address, calldata: Vec<u8>) -> bool {
+            self.evm.env.tx.transact_to = TransactTo::Call(address);
+            self.evm.env.tx.data = calldata.into();
+            matches!(
+                self.evm.transact_commit().unwrap(),
+                ExecutionResult::Success { .. }
+            )
+        }
+    }
+
+    #[test]
+    fn test_runtime_code() {
+        let yul = r#"
+            object "plonk_verifier" {
+                code {
+                    let size := datasize("Runtime")
+                    codecopy(0, dataoffset("Runtime"), size)
+                    return(0, size)
+                }
+                object "Runtime" {
+                    code {
+                        if iszero(success) { revert(0, 0) }
+                        return(0, 0)
+                    }
+                }
+            }"#;
+        let runtime = runtime_code(yul).unwrap();
+        assert_eq!(
+            runtime.split_whitespace().collect::<Vec<_>>().join(" "),
+            "if iszero(success) { revert(0, 0) } return(0, 0)"
+        );
+        assert_eq!(runtime_code("object \"plonk_verifier\" { code { } }"), None);
+    }
+
+    // The verifier contract is compiled with solc, so run this with `cargo test --features evm
+    // -- --ignored` where solc is installed
+    #[test]
+    #[ignore = "needs solc on the PATH"]
+    fn test_evm_verifier() {
+        let puzzle = fixtures::puzzle();
+        let solution = fixtures::solution();
+
+        let params = ParamsKZG::<Bn256>::setup_params(K, OsRng);
+        let (pk, vk) = keygen(&params).unwrap();
+        let solver = Some(Fr::from(0xa11ce_u64));
+        let circuit = SudokuCircuit::new(puzzle.clone(), solution)
+            .unwrap()
+            .with_solver_id(solver.unwrap());
+        let proof = prove_for_evm(&params, &pk, circuit, OsRng).unwrap();
+
+        let mut evm = Evm::new();
+        let verifier = evm.deploy(gen_evm_verifier(&params, &vk).unwrap());
+        assert!(evm.call(verifier, encode_calldata(&puzzle, solver, &proof)));
+
+        let other_solver = Some(Fr::from(0xb0b_u64));
+        assert!(!evm.call(verifier, encode_calldata(&puzzle, other_solver, &proof)));
+
+        let mut other_puzzle = puzzle.cells().clone();
+        other_puzzle[[0, 0]] = 4;
+        let other_puzzle = Puzzle::new(other_puzzle).unwrap();
+        assert!(!evm.call(verifier, encode_calldata(&other_puzzle, solver, &proof)));
+    }
+}
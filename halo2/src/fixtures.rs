@@ -0,0 +1,30 @@
+//! The example board shared by the tests, read from `puzzles/example.txt`.
+
+use crate::{board::PuzzleReader, Puzzle, Solution};
+use ndarray::Array2;
+
+const EXAMPLE: &str = include_str!("../puzzles/example.txt");
+
+// example_boards reads the example puzzle and its solution, in that order
+fn example_boards() -> impl Iterator<Item = Puzzle> {
+    PuzzleReader::new(EXAMPLE.as_bytes()).map(|board| board.expect("the example should parse"))
+}
+
+pub(crate) fn puzzle() -> Puzzle {
+    example_boards().next().unwrap()
+}
+
+pub(crate) fn solution() -> Solution {
+    let cells = example_boards().nth(1).unwrap().cells().clone();
+    Solution::new(cells).unwrap()
+}
+
+// puzzle_cells and solution_cells return the example's cells, for tests that tamper with them
+// or build circuits without checking them
+pub(crate) fn puzzle_cells() -> Array2<u8> {
+    puzzle().cells().clone()
+}
+
+pub(crate) fn solution_cells() -> Array2<u8> {
+    solution().cells().clone()
+}
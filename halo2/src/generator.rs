@@ -0,0 +1,483 @@
+//! Generation of fresh puzzles with a unique solution, rated by difficulty.
+//!
+//! Generation is deterministic from a seed, so that anyone can re-derive a published puzzle
+//! from its seed and audit it. A random solution is made by shuffling the digits, rows and
+//! columns of a fixed valid board in ways that keep it valid. Clues are then removed from it
+//! in a random order, skipping any removal that would leave the puzzle with more than one
+//! solution (as found by the native [`solver`](crate::solver)).
+//!
+//! Puzzles are rated by the hardest technique a human solver needs to solve them, from naked
+//! singles up to X-wings. Puzzles that none of the techniques below can finish need
+//! backtracking.
+
+use crate::{
+    solver::{self, cell_groups},
+    Puzzle, Solution, SudokuCircuit,
+};
+use halo2wrong::halo2::arithmetic::FieldExt;
+use ndarray::Array2;
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+/// The solving techniques puzzles are rated by, from easiest to hardest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Technique {
+    /// A cell with a single candidate digit
+    NakedSingle,
+    /// A digit with a single possible cell in some row, column or box
+    HiddenSingle,
+    /// A digit whose candidates within a box all lie in one row or column, or the other way
+    /// around, can be removed from the rest of that row or column (or box)
+    LockedCandidates,
+    /// Two cells of a group with the same two candidates take those two digits, so they can
+    /// be removed from the rest of the group
+    NakedPair,
+    /// Two digits with the same two possible cells in a group must go in those cells, so no
+    /// other digit can
+    HiddenPair,
+    /// A digit with the same two possible columns in two rows must go in those columns in
+    /// those rows, so it can be removed from the rest of both columns (and likewise with rows
+    /// and columns swapped)
+    XWing,
+    /// None of the techniques above make progress, and the solver has to guess
+    Backtracking,
+}
+
+/// A coarser rating of a puzzle's difficulty.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Difficulty {
+    /// Solvable with singles alone
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+    /// Needs more than the techniques the rater knows about
+    Extreme,
+}
+
+impl Technique {
+    pub fn difficulty(self) -> Difficulty {
+        match self {
+            Technique::NakedSingle | Technique::HiddenSingle => Difficulty::Easy,
+            Technique::LockedCandidates => Difficulty::Medium,
+            Technique::NakedPair | Technique::HiddenPair => Difficulty::Hard,
+            Technique::XWing => Difficulty::Expert,
+            Technique::Backtracking => Difficulty::Extreme,
+        }
+    }
+}
+
+// Candidates is a partially solved board, along with the digits each blank cell can still
+// hold. Digit `d` is bit `d - 1` of each mask
+struct Candidates<const N: usize> {
+    cells: Vec<u8>,
+    candidates: Vec<u64>,
+    groups: Vec<Vec<usize>>,
+    // the pairs of groups sharing more than one cell, i.e. each box with each row and column
+    // crossing it
+    overlaps: Vec<(usize, usize)>,
+}
+
+impl<const N: usize> Candidates<N> {
+    fn new(puzzle: &Puzzle<N>) -> Self {
+        let groups = cell_groups::<N>();
+        let mut overlaps = vec![];
+        for (a, group_a) in groups.iter().enumerate() {
+            for (b, group_b) in groups.iter().enumerate() {
+                let shared = group_a.iter().filter(|index| group_b.contains(index));
+                if a != b && shared.count() > 1 {
+                    overlaps.push((a, b));
+                }
+            }
+        }
+
+        let mut candidates = Candidates {
+            cells: vec![0; N * N],
+            candidates: vec![u64::MAX >> (64 - N); N * N],
+            groups,
+            overlaps,
+        };
+        for (index, &clue) in puzzle.cells().iter().enumerate() {
+            if clue != 0 {
+                candidates.place(index, clue);
+            }
+        }
+        candidates
+    }
+
+    fn is_solved(&self) -> bool {
+        self.cells.iter().all(|&cell| cell != 0)
+    }
+
+    fn place(&mut self, index: usize, digit: u8) {
+        self.cells[index] = digit;
+        self.candidates[index] = 0;
+        for group in &self.groups {
+            if group.contains(&index) {
+                for &peer in group {
+                    self.candidates[peer] &= !(1 << (digit - 1));
+                }
+            }
+        }
+    }
+
+    // eliminate removes the digits in `mask` from the candidates of the cell at `index`,
+    // returning whether any were removed
+    fn eliminate(&mut self, index: usize, mask: u64) -> bool {
+        let before = self.candidates[index];
+        self.candidates[index] &= !mask;
+        self.candidates[index] != before
+    }
+
+    // cells_with returns the cells of the given group that can hold any digit in `mask`
+    fn cells_with(&self, group: usize, mask: u64) -> Vec<usize> {
+        self.groups[group]
+            .iter()
+            .copied()
+            .filter(|&index| self.candidates[index] & mask != 0)
+            .collect()
+    }
+
+    // step applies the easiest technique that makes progress, returning it
+    fn step(&mut self) -> Option<Technique> {
+        if self.naked_single() {
+            Some(Technique::NakedSingle)
+        } else if self.hidden_single() {
+            Some(Technique::HiddenSingle)
+        } else if self.locked_candidates() {
+            Some(Technique::LockedCandidates)
+        } else if self.naked_pair() {
+            Some(Technique::NakedPair)
+        } else if self.hidden_pair() {
+            Some(Technique::HiddenPair)
+        } else if self.x_wing() {
+            Some(Technique::XWing)
+        } else {
+            None
+        }
+    }
+
+    fn naked_single(&mut self) -> bool {
+        let single = (0..N * N).find(|&index| self.candidates[index].count_ones() == 1);
+        match single {
+            Some(index) => {
+                let digit = self.candidates[index].trailing_zeros() as u8 + 1;
+                self.place(index, digit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn hidden_single(&mut self) -> bool {
+        for group in 0..3 * N {
+            for digit in 1..=N as u8 {
+                if let [index] = self.cells_with(group, 1 << (digit - 1))[..] {
+                    self.place(index, digit);
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    fn locked_candidates(&mut self) -> bool {
+        for (a, b) in self.overlaps.clone() {
+            for digit in 0..N {
+                let bit = 1 << digit;
+                let cells = self.cells_with(a, bit);
+                if cells.len() < 2 || !cells.iter().all(|index| self.groups[b].contains(index)) {
+                    continue;
+                }
+
+                let mut progress = false;
+                for index in self.groups[b].clone() {
+                    if !self.groups[a].contains(&index) {
+                        progress |= self.eliminate(index, bit);
+                    }
+                }
+                if progress {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    fn naked_pair(&mut self) -> bool {
+        for group in self.groups.clone() {
+            for (i, &first) in group.iter().enumerate() {
+                let pair = self.candidates[first];
+                if pair.count_ones() != 2 {
+                    continue;
+                }
+                let second = group[i + 1..]
+                    .iter()
+                    .find(|&&index| self.candidates[index] == pair);
+                if let Some(&second) = second {
+                    let mut progress = false;
+                    for &index in &group {
+                        if index != first && index != second {
+                            progress |= self.eliminate(index, pair);
+                        }
+                    }
+                    if progress {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    fn hidden_pair(&mut self) -> bool {
+        for group in 0..3 * N {
+            for first in 0..N {
+                let cells = self.cells_with(group, 1 << first);
+                if cells.len() != 2 {
+                    continue;
+                }
+                for second in first + 1..N {
+                    if self.cells_with(group, 1 << second) != cells {
+                        continue;
+                    }
+
+                    let others = !((1 << first) | (1 << second));
+                    let mut progress = false;
+                    for &index in &cells {
+                        progress |= self.eliminate(index, others);
+                    }
+                    if progress {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    fn x_wing(&mut self) -> bool {
+        // rows are groups 0..N and columns N..2N. The cell at position `i` of row `r` lies
+        // on column `i`, and the other way around
+        for (lines, crosses) in [(0, N), (N, 0)] {
+            for digit in 0..N {
+                let bit = 1 << digit;
+                let positions: Vec<Vec<usize>> = (0..N)
+                    .map(|line| {
+                        let group = &self.groups[lines + line];
+                        (0..N)
+                            .filter(|&i| self.candidates[group[i]] & bit != 0)
+                            .collect()
+                    })
+                    .collect();
+
+                for first in 0..N {
+                    if positions[first].len() != 2 {
+                        continue;
+                    }
+                    for second in first + 1..N {
+                        if positions[second] != positions[first] {
+                            continue;
+                        }
+
+                        let mut progress = false;
+                        for &cross in &positions[first] {
+                            for index in self.groups[crosses + cross].clone() {
+                                let in_wing = self.groups[lines + first].contains(&index)
+                                    || self.groups[lines + second].contains(&index);
+                                if !in_wing {
+                                    progress |= self.eliminate(index, bit);
+                                }
+                            }
+                        }
+                        if progress {
+                            return true;
+                        }
+                    }
+                }
+            }
+        }
+        false
+    }
+}
+
+/// Rates `puzzle` by the hardest technique needed to solve it, applying the easiest technique
+/// that makes progress at each step. Assumes the puzzle has a unique solution.
+pub fn rate<const N: usize>(puzzle: &Puzzle<N>) -> Technique {
+    let mut candidates = Candidates::new(puzzle);
+    let mut hardest = Technique::NakedSingle;
+    while !candidates.is_solved() {
+        match candidates.step() {
+            Some(technique) => hardest = hardest.max(technique),
+            None => return Technique::Backtracking,
+        }
+    }
+    hardest
+}
+
+/// A generated puzzle, along with its unique solution and rating.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GeneratedPuzzle<const N: usize = 9> {
+    pub puzzle: Puzzle<N>,
+    pub solution: Solution<N>,
+    /// The hardest technique needed to solve the puzzle, see [`rate`]
+    pub hardest_technique: Technique,
+}
+
+impl<const N: usize> GeneratedPuzzle<N> {
+    pub fn difficulty(&self) -> Difficulty {
+        self.hardest_technique.difficulty()
+    }
+
+    /// Builds the circuit proving knowledge of the solution to the puzzle.
+    pub fn into_circuit<F: FieldExt>(self) -> SudokuCircuit<F, N> {
+        // the puzzle was made by removing clues from the solution, so they always agree
+        SudokuCircuit::new_unchecked(self.puzzle, self.solution)
+    }
+}
+
+// shuffled_lines returns a random order of the N rows (or columns) of a board that keeps
+// every box together: the bands of boxes are shuffled, then the lines within each band
+fn shuffled_lines<const N: usize>(rng: &mut impl Rng) -> Vec<usize> {
+    let box_size = crate::isqrt(N);
+    let mut bands: Vec<usize> = (0..box_size).collect();
+    bands.shuffle(rng);
+    bands
+        .into_iter()
+        .flat_map(|band| {
+            let mut lines: Vec<usize> = (band * box_size..(band + 1) * box_size).collect();
+            lines.shuffle(rng);
+            lines
+        })
+        .collect()
+}
+
+// random_solution shuffles the digits, rows and columns of a fixed valid board, and
+// transposes it half of the time. Each of these keeps the board valid
+fn random_solution<const N: usize>(rng: &mut impl Rng) -> Solution<N> {
+    let box_size = crate::isqrt(N);
+    let mut digits: Vec<u8> = (1..=N as u8).collect();
+    digits.shuffle(rng);
+    let rows = shuffled_lines::<N>(rng);
+    let cols = shuffled_lines::<N>(rng);
+    let transpose = rng.gen::<bool>();
+
+    let cells = Array2::from_shape_fn((N, N), |(row, col)| {
+        let (row, col) = if transpose { (col, row) } else { (row, col) };
+        let (row, col) = (rows[row], cols[col]);
+        digits[(box_size * (row % box_size) + row / box_size + col) % N]
+    });
+    Solution::new_unchecked(cells)
+}
+
+/// Generates a puzzle with a unique solution from `seed`. The same seed always gives the same
+/// puzzle.
+pub fn generate<const N: usize>(seed: u64) -> GeneratedPuzzle<N> {
+    let mut rng = ChaCha20Rng::seed_from_u64(seed);
+    let solution = random_solution::<N>(&mut rng);
+
+    let mut order: Vec<(usize, usize)> = (0..N)
+        .flat_map(|row| (0..N).map(move |col| (row, col)))
+        .collect();
+    order.shuffle(&mut rng);
+
+    let mut cells = solution.cells().clone();
+    for cell in order {
+        let clue = cells[cell];
+        cells[cell] = 0;
+        if solver::solutions(&Puzzle::new_unchecked(cells.clone()), 2).len() > 1 {
+            cells[cell] = clue;
+        }
+    }
+
+    let puzzle = Puzzle::new_unchecked(cells);
+    GeneratedPuzzle {
+        hardest_technique: rate(&puzzle),
+        puzzle,
+        solution,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{fixtures, public_inputs, K};
+    use halo2wrong::halo2::{dev::MockProver, halo2curves::bn256::Fr as Fp};
+
+    #[test]
+    fn test_generate() {
+        let generated = generate::<9>(7);
+        assert_eq!(
+            solver::solve(&generated.puzzle),
+            Ok(generated.solution.clone())
+        );
+        assert_eq!(generated.hardest_technique, rate(&generated.puzzle));
+
+        // removing any remaining clue would allow another solution
+        let cells = generated.puzzle.cells();
+        for ((row, col), &clue) in cells.indexed_iter() {
+            if clue != 0 {
+                let mut fewer_clues = cells.clone();
+                fewer_clues[[row, col]] = 0;
+                let puzzle = Puzzle::new(fewer_clues).unwrap();
+                assert_eq!(solver::solutions(&puzzle, 2).len(), 2);
+            }
+        }
+
+        let generated = generate::<4>(7);
+        assert_eq!(solver::solve(&generated.puzzle), Ok(generated.solution));
+    }
+
+    #[test]
+    fn test_generate_is_deterministic() {
+        assert_eq!(generate::<9>(1), generate::<9>(1));
+        assert_ne!(generate::<9>(1).puzzle, generate::<9>(2).puzzle);
+    }
+
+    #[test]
+    fn test_generated_puzzle_proves() {
+        let circuit = generate::<9>(3).into_circuit::<Fp>();
+        let public_inputs = public_inputs(circuit.puzzle(), circuit.solver_id());
+        let prover = MockProver::run(K, &circuit, public_inputs).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn test_rate() {
+        // an almost solved board needs nothing but a naked single
+        let mut cells = generate::<9>(5).solution.cells().clone();
+        cells[[4, 4]] = 0;
+        assert_eq!(
+            rate(&Puzzle::<9>::new(cells).unwrap()),
+            Technique::NakedSingle
+        );
+
+        // the example board the tests share is easy
+        assert_eq!(rate(&fixtures::puzzle()).difficulty(), Difficulty::Easy);
+
+        // Arto Inkala's puzzle needs chains of reasoning well beyond X-wings
+        let hardest: Puzzle =
+            "800000000003600000070090200050007000000045700000100030001000068008500010090000400"
+                .parse()
+                .unwrap();
+        assert_eq!(rate(&hardest), Technique::Backtracking);
+    }
+
+    #[test]
+    fn test_x_wing() {
+        // candidates for 1 in rows 0 and 3 are confined to columns 0 and 3, so 1 can be
+        // removed from the rest of those columns
+        let mut candidates = Candidates::<4>::new(&Puzzle::blank());
+        for row in [0, 3] {
+            for col in [1, 2] {
+                candidates.eliminate(row * 4 + col, 1);
+            }
+        }
+        assert!(candidates.x_wing());
+        for row in [1, 2] {
+            for col in [0, 3] {
+                assert_eq!(candidates.candidates[row * 4 + col] & 1, 0);
+            }
+        }
+    }
+}
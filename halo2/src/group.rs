@@ -0,0 +1,726 @@
+//! Checks that a row, column or box of a solution holds each of the digits 1..=N exactly once.
+//!
+//! The top-level README lists four ways of doing this, each available here as a
+//! [`GroupCheckMode`]:
+//! 1. [`GroupCheckMode::Fingerprint`]: compare the group against the set of digits as a
+//!    randomised fingerprint, `sum(1 / (gamma - cell)) == sum(1 / (gamma - digit))`
+//! 2. [`GroupCheckMode::PairwiseDistinct`]: check that the (range-checked) cells are pairwise
+//!    distinct and sum to `N * (N + 1) / 2`
+//! 3. [`GroupCheckMode::Shuffle`]: check that the group is a permutation of the digits with a
+//!    grand-product shuffle argument
+//! 4. [`GroupCheckMode::IndexHint`]: have the prover point out where each digit sits in the
+//!    group, and check that it is there
+//!
+//! On top of these, [`GroupCheckMode::MultisetProduct`] is a cheaper take on approach 3: it
+//! checks `prod(gamma - cell) == prod(gamma - digit)`, with the right-hand side computed once
+//! for the whole board.
+//!
+//! The challenge-based checks draw `gamma` from the verifier after the board has been
+//! committed to, so the values depending on it live in second-phase advice columns.
+
+use halo2wrong::{
+    halo2::{
+        arithmetic::FieldExt,
+        circuit::{AssignedCell, Layouter, Region, Value},
+        plonk::{
+            Advice, Challenge, Column, ConstraintSystem, Constraints, Error, Expression,
+            FirstPhase, Fixed, SecondPhase, Selector,
+        },
+        poly::Rotation,
+    },
+    RegionCtx,
+};
+use itertools::Itertools;
+use maingate::{MainGate, MainGateConfig, MainGateInstructions, Term};
+use std::fmt::Debug;
+
+/// The ways of checking that a group of cells holds each digit exactly once.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupCheckMode {
+    /// Sum check plus an `assert_not_equal` for each of the `N * (N - 1) / 2` pairs of cells.
+    /// Simple and deterministic, but its cost grows quadratically with the board size.
+    PairwiseDistinct,
+    /// A logarithmic-derivative fingerprint of the group under a verifier challenge. Costs
+    /// `N + 1` rows of a custom gate over three second-phase columns per group.
+    Fingerprint,
+    /// A grand-product shuffle argument between the group and the digits under a verifier
+    /// challenge. Costs `N + 1` rows of a custom gate over one second-phase column per group.
+    Shuffle,
+    /// Prover-supplied indices of each digit within the group, checked through a one-hot
+    /// encoding. Needs no challenge, but costs `N * N` boolean cells and products per group.
+    IndexHint,
+    /// Randomised multiset equality between the group and the digits under a verifier
+    /// challenge. Costs `N + 1` rows of a single product gate over one second-phase column
+    /// per group, plus `N + 1` rows once per board.
+    MultisetProduct,
+}
+
+/// Type-level choice of a [`GroupCheckMode`], so that `Circuit::configure` knows which group
+/// check to build.
+pub trait GroupCheckStrategy: Clone + Debug + Default {
+    const MODE: GroupCheckMode;
+}
+
+/// Selects [`GroupCheckMode::PairwiseDistinct`]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PairwiseDistinct;
+
+impl GroupCheckStrategy for PairwiseDistinct {
+    const MODE: GroupCheckMode = GroupCheckMode::PairwiseDistinct;
+}
+
+/// Selects [`GroupCheckMode::Fingerprint`]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Fingerprint;
+
+impl GroupCheckStrategy for Fingerprint {
+    const MODE: GroupCheckMode = GroupCheckMode::Fingerprint;
+}
+
+/// Selects [`GroupCheckMode::Shuffle`]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Shuffle;
+
+impl GroupCheckStrategy for Shuffle {
+    const MODE: GroupCheckMode = GroupCheckMode::Shuffle;
+}
+
+/// Selects [`GroupCheckMode::IndexHint`]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IndexHint;
+
+impl GroupCheckStrategy for IndexHint {
+    const MODE: GroupCheckMode = GroupCheckMode::IndexHint;
+}
+
+/// Selects [`GroupCheckMode::MultisetProduct`]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MultisetProduct;
+
+impl GroupCheckStrategy for MultisetProduct {
+    const MODE: GroupCheckMode = GroupCheckMode::MultisetProduct;
+}
+
+// ChallengeColumns are the columns shared by the challenge-based checks. Each group is laid
+// out in a region of its own, one cell per row next to the digit it is compared against,
+// with a final row closing the running sum or product
+#[derive(Clone, Debug)]
+struct ChallengeColumns {
+    // a copy of each cell of the group
+    cell: Column<Advice>,
+
+    // the digits 1..=N, one per row
+    digit: Column<Fixed>,
+
+    // the running sum or product, which must start and end at a known value
+    running: Column<Advice>,
+
+    // toggles the step from one row of the running sum or product to the next
+    step_selector: Selector,
+
+    // toggles the check of the running sum or product's first (and possibly last) value
+    bounds_selector: Selector,
+
+    gamma: Challenge,
+}
+
+impl ChallengeColumns {
+    fn configure<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+        let cell = meta.advice_column();
+        meta.enable_equality(cell);
+
+        ChallengeColumns {
+            cell,
+            digit: meta.fixed_column(),
+            running: meta.advice_column_in(SecondPhase),
+            step_selector: meta.selector(),
+            bounds_selector: meta.selector(),
+            gamma: meta.challenge_usable_after(FirstPhase),
+        }
+    }
+
+    // assign_group copies the cells of a group into the region and turns the step selector
+    // on for each of them, returning the group's values
+    fn assign_group<F: FieldExt>(
+        &self,
+        region: &mut Region<'_, F>,
+        cells: &[AssignedCell<F, F>],
+    ) -> Result<Vec<Value<F>>, Error> {
+        for (offset, cell) in cells.iter().enumerate() {
+            cell.copy_advice(|| "group cell", region, self.cell, offset)?;
+            self.step_selector.enable(region, offset)?;
+        }
+
+        Ok(cells.iter().map(|cell| cell.value().cloned()).collect())
+    }
+
+    // assign_digits fills the first `group_size` rows of the region's digit column with
+    // the digits 1..=N
+    fn assign_digits<F: FieldExt>(
+        &self,
+        region: &mut Region<'_, F>,
+        group_size: usize,
+    ) -> Result<(), Error> {
+        for offset in 0..group_size {
+            region.assign_fixed(
+                || "digit",
+                self.digit,
+                offset,
+                || Value::known(F::from(offset as u64 + 1)),
+            )?;
+        }
+        Ok(())
+    }
+
+    // enable_bounds turns the bounds selector on for the first and last rows of a region
+    // holding `group_size` steps
+    fn enable_bounds<F: FieldExt>(
+        &self,
+        region: &mut Region<'_, F>,
+        group_size: usize,
+    ) -> Result<(), Error> {
+        self.bounds_selector.enable(region, 0)?;
+        self.bounds_selector.enable(region, group_size)
+    }
+}
+
+// inverse_difference computes 1 / (gamma - value). The challenge is a random field element,
+// so it (almost surely) isn't a small number and the inverse exists
+fn inverse_difference<F: FieldExt>(gamma: Value<F>, value: Value<F>) -> Value<F> {
+    (gamma - value).map(|difference| difference.invert().unwrap_or_else(F::zero))
+}
+
+// FingerprintConfig checks sum(1 / (gamma - cell)) == sum(1 / (gamma - digit)) with a running
+// sum `acc` of the difference, which must start and end at zero
+#[derive(Clone, Debug)]
+struct FingerprintConfig {
+    columns: ChallengeColumns,
+
+    // 1 / (gamma - cell) on each row
+    cell_inverse: Column<Advice>,
+
+    // 1 / (gamma - digit) on each row
+    digit_inverse: Column<Advice>,
+}
+
+impl FingerprintConfig {
+    fn configure<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+        let columns = ChallengeColumns::configure(meta);
+        let cell_inverse = meta.advice_column_in(SecondPhase);
+        let digit_inverse = meta.advice_column_in(SecondPhase);
+
+        meta.create_gate("fingerprint", |meta| {
+            let selector = meta.query_selector(columns.step_selector);
+            let gamma = meta.query_challenge(columns.gamma);
+            let cell = meta.query_advice(columns.cell, Rotation::cur());
+            let digit = meta.query_fixed(columns.digit, Rotation::cur());
+            let cell_inverse = meta.query_advice(cell_inverse, Rotation::cur());
+            let digit_inverse = meta.query_advice(digit_inverse, Rotation::cur());
+            let acc = meta.query_advice(columns.running, Rotation::cur());
+            let acc_next = meta.query_advice(columns.running, Rotation::next());
+            let one = Expression::Constant(F::one());
+
+            Constraints::with_selector(
+                selector,
+                [
+                    cell_inverse.clone() * (gamma.clone() - cell) - one.clone(),
+                    digit_inverse.clone() * (gamma - digit) - one,
+                    acc_next - acc - cell_inverse + digit_inverse,
+                ],
+            )
+        });
+
+        meta.create_gate("fingerprint bounds", |meta| {
+            let selector = meta.query_selector(columns.bounds_selector);
+            let acc = meta.query_advice(columns.running, Rotation::cur());
+
+            Constraints::with_selector(selector, Some(acc))
+        });
+
+        FingerprintConfig {
+            columns,
+            cell_inverse,
+            digit_inverse,
+        }
+    }
+
+    fn check_group<F: FieldExt>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        name: &str,
+        cells: &[AssignedCell<F, F>],
+    ) -> Result<(), Error> {
+        let gamma = layouter.get_challenge(self.columns.gamma);
+
+        layouter.assign_region(
+            || name,
+            |mut region| {
+                let values = self.columns.assign_group(&mut region, cells)?;
+                self.columns.assign_digits(&mut region, cells.len())?;
+                self.columns.enable_bounds(&mut region, cells.len())?;
+
+                let mut acc = Value::known(F::zero());
+                for (offset, value) in values.into_iter().enumerate() {
+                    region.assign_advice(|| "acc", self.columns.running, offset, || acc)?;
+
+                    let cell_inverse = inverse_difference(gamma, value);
+                    let digit = Value::known(F::from(offset as u64 + 1));
+                    let digit_inverse = inverse_difference(gamma, digit);
+                    region.assign_advice(
+                        || "cell inverse",
+                        self.cell_inverse,
+                        offset,
+                        || cell_inverse,
+                    )?;
+                    region.assign_advice(
+                        || "digit inverse",
+                        self.digit_inverse,
+                        offset,
+                        || digit_inverse,
+                    )?;
+                    acc = acc + cell_inverse - digit_inverse;
+                }
+                region.assign_advice(|| "acc", self.columns.running, cells.len(), || acc)?;
+
+                Ok(())
+            },
+        )
+    }
+}
+
+// RunningProductConfig lays out a running product z of (gamma - cell) over a group, starting
+// at 1, and is shared by the product-based checks. With `divide_by_digits` each step also
+// divides by (gamma - digit), z[i + 1] * (gamma - digit[i]) == z[i] * (gamma - cell[i]), so
+// that z ends back at 1 exactly when the group is a shuffle of the digits. Without it,
+// z[i + 1] == z[i] * (gamma - cell[i]) and z ends at prod(gamma - cell)
+#[derive(Clone, Debug)]
+struct RunningProductConfig {
+    columns: ChallengeColumns,
+    divide_by_digits: bool,
+}
+
+impl RunningProductConfig {
+    fn configure<F: FieldExt>(
+        meta: &mut ConstraintSystem<F>,
+        name: &'static str,
+        divide_by_digits: bool,
+    ) -> Self {
+        let columns = ChallengeColumns::configure(meta);
+
+        meta.create_gate(name, |meta| {
+            let selector = meta.query_selector(columns.step_selector);
+            let gamma = meta.query_challenge(columns.gamma);
+            let cell = meta.query_advice(columns.cell, Rotation::cur());
+            let z = meta.query_advice(columns.running, Rotation::cur());
+            let mut z_next = meta.query_advice(columns.running, Rotation::next());
+            if divide_by_digits {
+                let digit = meta.query_fixed(columns.digit, Rotation::cur());
+                z_next = z_next * (gamma.clone() - digit);
+            }
+
+            Constraints::with_selector(selector, Some(z_next - z * (gamma - cell)))
+        });
+
+        // z starts at 1, and when dividing by the digits also ends there
+        meta.create_gate("running product bounds", |meta| {
+            let selector = meta.query_selector(columns.bounds_selector);
+            let z = meta.query_advice(columns.running, Rotation::cur());
+
+            Constraints::with_selector(selector, Some(z - Expression::Constant(F::one())))
+        });
+
+        RunningProductConfig {
+            columns,
+            divide_by_digits,
+        }
+    }
+
+    // assign_running_product lays out the running product over a group, and returns its last
+    // value
+    fn assign_running_product<F: FieldExt>(
+        &self,
+        region: &mut Region<'_, F>,
+        gamma: Value<F>,
+        cells: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let values = self.columns.assign_group(region, cells)?;
+        self.columns.bounds_selector.enable(region, 0)?;
+
+        let mut z = Value::known(F::one());
+        for (offset, value) in values.into_iter().enumerate() {
+            region.assign_advice(|| "z", self.columns.running, offset, || z)?;
+
+            z = z * (gamma - value);
+            if self.divide_by_digits {
+                let digit = Value::known(F::from(offset as u64 + 1));
+                z = z * inverse_difference(gamma, digit);
+            }
+        }
+        region.assign_advice(|| "z", self.columns.running, cells.len(), || z)
+    }
+}
+
+// ShuffleConfig checks that the group is a permutation of the digits with a running product
+// that divides by the digits on each row, and must start and end at 1
+#[derive(Clone, Debug)]
+struct ShuffleConfig {
+    product: RunningProductConfig,
+}
+
+impl ShuffleConfig {
+    fn configure<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+        ShuffleConfig {
+            product: RunningProductConfig::configure(meta, "shuffle", true),
+        }
+    }
+
+    fn check_group<F: FieldExt>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        name: &str,
+        cells: &[AssignedCell<F, F>],
+    ) -> Result<(), Error> {
+        let columns = &self.product.columns;
+        let gamma = layouter.get_challenge(columns.gamma);
+
+        layouter.assign_region(
+            || name,
+            |mut region| {
+                self.product
+                    .assign_running_product(&mut region, gamma, cells)?;
+                columns.assign_digits(&mut region, cells.len())?;
+                columns.enable_bounds(&mut region, cells.len())
+            },
+        )
+    }
+}
+
+// MultisetProductConfig checks prod(gamma - cell) == prod(gamma - digit) with running
+// products starting at 1. The product over the digits is the same for every group, so it is
+// computed once in a region of its own, and the last value of each group's running product
+// is copy constrained to it
+#[derive(Clone, Debug)]
+struct MultisetProductConfig {
+    product: RunningProductConfig,
+
+    // toggles the step of the running product over the digits
+    digit_step_selector: Selector,
+}
+
+impl MultisetProductConfig {
+    fn configure<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+        let product = RunningProductConfig::configure(meta, "multiset product", false);
+        let columns = &product.columns;
+        meta.enable_equality(columns.running);
+        let digit_step_selector = meta.selector();
+
+        meta.create_gate("multiset product of digits", |meta| {
+            let selector = meta.query_selector(digit_step_selector);
+            let gamma = meta.query_challenge(columns.gamma);
+            let digit = meta.query_fixed(columns.digit, Rotation::cur());
+            let z = meta.query_advice(columns.running, Rotation::cur());
+            let z_next = meta.query_advice(columns.running, Rotation::next());
+
+            Constraints::with_selector(selector, Some(z_next - z * (gamma - digit)))
+        });
+
+        MultisetProductConfig {
+            product,
+            digit_step_selector,
+        }
+    }
+
+    fn check_groups<F: FieldExt>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        group_size: usize,
+        names: &[String],
+        groups: &[Vec<AssignedCell<F, F>>],
+    ) -> Result<(), Error> {
+        let columns = &self.product.columns;
+        let gamma = layouter.get_challenge(columns.gamma);
+
+        let digits_product = layouter.assign_region(
+            || "multiset product of digits",
+            |mut region| {
+                columns.assign_digits(&mut region, group_size)?;
+                columns.bounds_selector.enable(&mut region, 0)?;
+
+                let mut z = Value::known(F::one());
+                for offset in 0..group_size {
+                    self.digit_step_selector.enable(&mut region, offset)?;
+                    region.assign_advice(|| "z", columns.running, offset, || z)?;
+
+                    let digit = Value::known(F::from(offset as u64 + 1));
+                    z = z * (gamma - digit);
+                }
+                region.assign_advice(|| "z", columns.running, group_size, || z)
+            },
+        )?;
+
+        for (name, cells) in names.iter().zip_eq(groups) {
+            layouter.assign_region(
+                || name,
+                |mut region| {
+                    let product = self
+                        .product
+                        .assign_running_product(&mut region, gamma, cells)?;
+                    region.constrain_equal(product.cell(), digits_product.cell())
+                },
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+// The columns and gates backing each `GroupCheckMode`, split by whether the groups are
+// checked one at a time or all together
+#[derive(Clone, Debug)]
+enum GroupCheckGadget {
+    PerGroup(PerGroupGadget),
+    MultisetProduct(MultisetProductConfig),
+}
+
+// The gadgets checking each group on its own. The modes built on top of `maingate` need
+// nothing beyond its config
+#[derive(Clone, Debug)]
+enum PerGroupGadget {
+    PairwiseDistinct,
+    Fingerprint(FingerprintConfig),
+    Shuffle(ShuffleConfig),
+    IndexHint,
+}
+
+/// GroupCheckConfig holds whatever columns, gates and challenges the chosen
+/// [`GroupCheckMode`] needs to check the groups of an NxN board.
+#[derive(Clone, Debug)]
+pub struct GroupCheckConfig {
+    main_gate_config: MainGateConfig,
+
+    // the number of cells in a group, which is also the largest digit
+    group_size: usize,
+
+    gadget: GroupCheckGadget,
+}
+
+impl GroupCheckConfig {
+    pub fn configure<F: FieldExt>(
+        meta: &mut ConstraintSystem<F>,
+        main_gate_config: MainGateConfig,
+        group_size: usize,
+        mode: GroupCheckMode,
+    ) -> Self {
+        let gadget = match mode {
+            GroupCheckMode::PairwiseDistinct => {
+                GroupCheckGadget::PerGroup(PerGroupGadget::PairwiseDistinct)
+            }
+            GroupCheckMode::Fingerprint => GroupCheckGadget::PerGroup(PerGroupGadget::Fingerprint(
+                FingerprintConfig::configure(meta),
+            )),
+            GroupCheckMode::Shuffle => {
+                GroupCheckGadget::PerGroup(PerGroupGadget::Shuffle(ShuffleConfig::configure(meta)))
+            }
+            GroupCheckMode::IndexHint => GroupCheckGadget::PerGroup(PerGroupGadget::IndexHint),
+            GroupCheckMode::MultisetProduct => {
+                GroupCheckGadget::MultisetProduct(MultisetProductConfig::configure(meta))
+            }
+        };
+
+        GroupCheckConfig {
+            main_gate_config,
+            group_size,
+            gadget,
+        }
+    }
+
+    pub fn mode(&self) -> GroupCheckMode {
+        match &self.gadget {
+            GroupCheckGadget::PerGroup(PerGroupGadget::PairwiseDistinct) => {
+                GroupCheckMode::PairwiseDistinct
+            }
+            GroupCheckGadget::PerGroup(PerGroupGadget::Fingerprint(_)) => {
+                GroupCheckMode::Fingerprint
+            }
+            GroupCheckGadget::PerGroup(PerGroupGadget::Shuffle(_)) => GroupCheckMode::Shuffle,
+            GroupCheckGadget::PerGroup(PerGroupGadget::IndexHint) => GroupCheckMode::IndexHint,
+            GroupCheckGadget::MultisetProduct(_) => GroupCheckMode::MultisetProduct,
+        }
+    }
+
+    fn main_gate<F: FieldExt>(&self) -> MainGate<F> {
+        MainGate::<F>::new(self.main_gate_config.clone())
+    }
+
+    // group_sum is the sum of the digits 1..=N
+    fn group_sum<F: FieldExt>(&self) -> F {
+        F::from((self.group_size * (self.group_size + 1) / 2) as u64)
+    }
+
+    /// Checks that each of the given groups of cells (the rows, columns and boxes of a board)
+    /// holds each digit 1..=N exactly once. Each group is checked in a region named after it
+    /// (e.g. "row 3"), so that failures can be traced back to the group. `digit_positions`
+    /// holds the prover's hint of where each digit sits in each group (see
+    /// [`digit_positions`]), and is only read in [`GroupCheckMode::IndexHint`]. A group that
+    /// doesn't hold N cells, or in that mode a hint missing a group or a digit, fails synthesis
+    /// with [`Error::Synthesis`].
+    pub fn check_groups<F: FieldExt>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        names: &[String],
+        groups: &[Vec<AssignedCell<F, F>>],
+        digit_positions: &[Vec<usize>],
+    ) -> Result<(), Error> {
+        if groups.iter().any(|cells| cells.len() != self.group_size) {
+            return Err(Error::Synthesis);
+        }
+
+        let gadget = match &self.gadget {
+            GroupCheckGadget::PerGroup(gadget) => gadget,
+            GroupCheckGadget::MultisetProduct(config) => {
+                return config.check_groups(layouter, self.group_size, names, groups);
+            }
+        };
+
+        if self.mode() == GroupCheckMode::IndexHint && digit_positions.len() != groups.len() {
+            return Err(Error::Synthesis);
+        }
+
+        for (i, (name, cells)) in names.iter().zip_eq(groups).enumerate() {
+            let digit_positions = digit_positions.get(i).map_or(&[][..], Vec::as_slice);
+            self.check_group(gadget, layouter, name, cells, digit_positions)?;
+        }
+        Ok(())
+    }
+
+    // check_group checks a single group with the given gadget, in a region of its own
+    fn check_group<F: FieldExt>(
+        &self,
+        gadget: &PerGroupGadget,
+        layouter: &mut impl Layouter<F>,
+        name: &str,
+        cells: &[AssignedCell<F, F>],
+        digit_positions: &[usize],
+    ) -> Result<(), Error> {
+        match gadget {
+            PerGroupGadget::PairwiseDistinct => layouter.assign_region(
+                || name,
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    self.check_pairwise_distinct(ctx, cells)
+                },
+            ),
+            PerGroupGadget::Fingerprint(config) => config.check_group(layouter, name, cells),
+            PerGroupGadget::Shuffle(config) => config.check_group(layouter, name, cells),
+            PerGroupGadget::IndexHint => layouter.assign_region(
+                || name,
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    self.check_index_hints(ctx, cells, digit_positions)
+                },
+            ),
+        }
+    }
+
+    // check_pairwise_distinct assumes all values in the given cells are within [1, N],
+    // and will check that all values are pairwise distinct and sum to N * (N + 1) / 2.
+    // The sum is redundant given the range and uniqueness checks, but it is cheap and
+    // keeps this group check sound on its own even for cells only constrained to [0, N]
+    fn check_pairwise_distinct<F: FieldExt>(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        cells: &[AssignedCell<F, F>],
+    ) -> Result<(), Error> {
+        let main_gate = self.main_gate();
+
+        // Check sum of cells == N * (N + 1) / 2 (45 on a 9x9 board)
+        // Do this by checking that the sum of the cells plus its negation equals zero
+        let terms: Vec<Term<F>> = cells.iter().map(Term::assigned_to_add).collect();
+        main_gate.assert_zero_sum(ctx, &terms, self.group_sum::<F>().neg())?;
+
+        // Now check that all cells are unique by asserting every pair is non-equal.
+        // Comparing only adjacent pairs is not enough: a group like 9,1,9,1,9,1,9,1,5
+        // has no equal neighbours and still sums to 45 (N = 9)
+        for (cell_a, cell_b) in cells.iter().tuple_combinations() {
+            main_gate.assert_not_equal(ctx, cell_a, cell_b)?;
+        }
+
+        Ok(())
+    }
+
+    // check_index_hints takes the prover's word for the index in the group holding each
+    // digit, and checks that the cell at that index holds the digit. The index is witnessed
+    // as a one-hot encoding: N bits, exactly one of them set, selecting the cell. As N
+    // distinct digits are then found among N cells, each digit appears exactly once. An out
+    // of range index leaves every bit unset, which fails the one-hot check, while a hint
+    // without an index for every digit can't be laid out at all
+    fn check_index_hints<F: FieldExt>(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        cells: &[AssignedCell<F, F>],
+        digit_positions: &[usize],
+    ) -> Result<(), Error> {
+        if digit_positions.len() != self.group_size {
+            return Err(Error::Synthesis);
+        }
+        let main_gate = self.main_gate();
+
+        for (digit, &position) in (1..=self.group_size as u64).zip(digit_positions) {
+            let digit = F::from(digit);
+
+            let mut bits = vec![];
+            let mut selected = vec![];
+            for (cell_index, cell) in cells.iter().enumerate() {
+                let bit = if position == cell_index {
+                    F::one()
+                } else {
+                    F::zero()
+                };
+                let bit = main_gate.assign_bit(ctx, Value::known(bit))?;
+                selected.push(main_gate.mul(ctx, &bit, cell)?);
+                bits.push(bit);
+            }
+
+            // exactly one bit is set...
+            let bit_terms: Vec<Term<F>> = bits.iter().map(Term::assigned_to_add).collect();
+            main_gate.assert_zero_sum(ctx, &bit_terms, F::one().neg())?;
+
+            // ...and the cell it selects holds the digit
+            let selected_terms: Vec<Term<F>> = selected.iter().map(Term::assigned_to_add).collect();
+            main_gate.assert_zero_sum(ctx, &selected_terms, digit.neg())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Generates the prover's hint for [`GroupCheckMode::IndexHint`] from the values of a group:
+/// the index of each digit 1..=N within it. Digits missing from the group are given the
+/// out of range index N, which the circuit rejects.
+pub fn digit_positions(group: &[u8]) -> Vec<usize> {
+    (1..=group.len())
+        .map(|digit| {
+            group
+                .iter()
+                .position(|&value| usize::from(value) == digit)
+                .unwrap_or(group.len())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_digit_positions() {
+        assert_eq!(
+            digit_positions(&[4, 3, 5, 2, 6, 9, 7, 8, 1]),
+            vec![8, 3, 1, 0, 2, 4, 6, 7, 5]
+        );
+        assert_eq!(digit_positions(&[2, 1, 4, 3]), vec![1, 0, 3, 2]);
+
+        // 3 is missing, and only the first of the two 2s is pointed out
+        assert_eq!(digit_positions(&[2, 1, 4, 2]), vec![1, 0, 4, 2]);
+    }
+}
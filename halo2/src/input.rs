@@ -0,0 +1,238 @@
+//! The JSON input format shared with the circom implementation (see
+//! `circom/circuits/sudoku.json`), so that the same fixtures drive every implementation in
+//! this repo:
+//!
+//! ```json
+//! {
+//!     "puzzle": [0, 0, 0, 2, 6, 0, 7, 0, 1, ...],
+//!     "solution": [4, 3, 5, 2, 6, 9, 7, 8, 1, ...],
+//!     "solverAddress": "261024854719013521818605857561652274258213999084"
+//! }
+//! ```
+//!
+//! The boards are flat arrays of their 81 cells in row-major order, and the solver address is
+//! a decimal string, which becomes the circuit's solver id. It may be left out for proofs that
+//! aren't bound to a solver.
+
+use crate::{BoardError, Puzzle, Solution, SudokuCircuit};
+use halo2wrong::halo2::arithmetic::FieldExt;
+use ndarray::Array2;
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+/// The reasons an input file can fail to load.
+#[derive(Debug)]
+pub enum InputError {
+    Io(io::Error),
+    /// The file isn't JSON, or is missing some of the expected fields
+    Json(serde_json::Error),
+    Board(BoardError),
+    /// The solver address isn't a decimal number
+    InvalidSolverAddress(String),
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputError::Io(err) => write!(f, "{}", err),
+            InputError::Json(err) => write!(f, "{}", err),
+            InputError::Board(err) => write!(f, "invalid board: {}", err),
+            InputError::InvalidSolverAddress(address) => {
+                write!(f, "solver address {:?} is not a decimal number", address)
+            }
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+impl From<io::Error> for InputError {
+    fn from(err: io::Error) -> Self {
+        InputError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for InputError {
+    fn from(err: serde_json::Error) -> Self {
+        InputError::Json(err)
+    }
+}
+
+impl From<BoardError> for InputError {
+    fn from(err: BoardError) -> Self {
+        InputError::Board(err)
+    }
+}
+
+// The file as it is laid out on disk
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InputFile {
+    puzzle: Vec<u8>,
+    solution: Vec<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    solver_address: Option<String>,
+}
+
+// board_cells arranges the flat cells of a board into a 9x9 board
+fn board_cells(cells: Vec<u8>) -> Result<Array2<u8>, BoardError> {
+    let found = cells.len();
+    Array2::from_shape_vec((9, 9), cells).map_err(|_| BoardError::WrongLength {
+        expected: 81,
+        found,
+    })
+}
+
+/// A puzzle, its solution and the solver to bind the proof to, as read from an input file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SudokuInput<F: FieldExt> {
+    pub puzzle: Puzzle,
+    pub solution: Solution,
+    pub solver_address: Option<F>,
+}
+
+impl<F: FieldExt> SudokuInput<F> {
+    pub fn read(reader: impl Read) -> Result<Self, InputError> {
+        let file: InputFile = serde_json::from_reader(reader)?;
+        let solver_address = match file.solver_address {
+            Some(address) => Some(
+                F::from_str_vartime(&address).ok_or(InputError::InvalidSolverAddress(address))?,
+            ),
+            None => None,
+        };
+
+        Ok(SudokuInput {
+            puzzle: Puzzle::new(board_cells(file.puzzle)?)?,
+            solution: Solution::new(board_cells(file.solution)?)?,
+            solver_address,
+        })
+    }
+
+    pub fn write(&self, writer: impl Write) -> Result<(), InputError> {
+        let file = InputFile {
+            puzzle: self.puzzle.cells().iter().copied().collect(),
+            solution: self.solution.cells().iter().copied().collect(),
+            solver_address: self
+                .solver_address
+                .map(|address| BigUint::from_bytes_le(address.to_repr().as_ref()).to_string()),
+        };
+
+        Ok(serde_json::to_writer_pretty(writer, &file)?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, InputError> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), InputError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        Ok(writer.flush()?)
+    }
+
+    /// Builds the circuit proving knowledge of the solution, bound to the solver address if
+    /// there is one.
+    pub fn into_circuit(self) -> Result<SudokuCircuit<F>, BoardError> {
+        let circuit = SudokuCircuit::new(self.puzzle, self.solution)?;
+        Ok(match self.solver_address {
+            Some(address) => circuit.with_solver_id(address),
+            None => circuit,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{fixtures, keygen, prove, public_inputs, verify, CommitmentParams, K};
+    use halo2wrong::halo2::{
+        dev::MockProver,
+        halo2curves::bn256::{Bn256, Fr},
+        poly::kzg::commitment::ParamsKZG,
+    };
+    use rand::rngs::OsRng;
+
+    const CIRCOM_INPUT: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../circom/circuits/sudoku.json"
+    );
+
+    #[test]
+    fn test_prove_circom_input() {
+        let input = SudokuInput::<Fr>::load(CIRCOM_INPUT).unwrap();
+        assert_eq!(input.puzzle, fixtures::puzzle());
+        let solver = input.solver_address;
+        assert!(solver.is_some());
+
+        let circuit = input.clone().into_circuit().unwrap();
+        let prover = MockProver::run(
+            K,
+            &circuit,
+            public_inputs(circuit.puzzle(), circuit.solver_id()),
+        )
+        .unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let params = ParamsKZG::<Bn256>::setup_params(K, OsRng);
+        let (pk, vk) = keygen(&params).unwrap();
+        let proof = prove(&params, &pk, circuit, OsRng).unwrap();
+        assert!(verify(&params, &vk, &input.puzzle, solver, &proof).is_ok());
+    }
+
+    #[test]
+    fn test_write_circom_input() {
+        let input = SudokuInput::<Fr>::load(CIRCOM_INPUT).unwrap();
+
+        let mut bytes = vec![];
+        input.write(&mut bytes).unwrap();
+        assert_eq!(SudokuInput::read(bytes.as_slice()).unwrap(), input);
+
+        // the solver address is written back exactly as circom expects it
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(
+            json["solverAddress"],
+            "261024854719013521818605857561652274258213999084"
+        );
+
+        let input = SudokuInput {
+            solver_address: None,
+            ..input
+        };
+        let mut bytes = vec![];
+        input.write(&mut bytes).unwrap();
+        assert_eq!(SudokuInput::read(bytes.as_slice()).unwrap(), input);
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let result = SudokuInput::<Fr>::read(r#"{"puzzle": [0, 0], "solution": []}"#.as_bytes());
+        assert!(matches!(
+            result,
+            Err(InputError::Board(BoardError::WrongLength {
+                expected: 81,
+                found: 2
+            }))
+        ));
+
+        let cells = ["1"; 81].join(",");
+        let json = format!(
+            r#"{{"puzzle": [{}], "solution": [{}], "solverAddress": "0x2Db8"}}"#,
+            cells, cells
+        );
+        assert!(matches!(
+            SudokuInput::<Fr>::read(json.as_bytes()),
+            Err(InputError::InvalidSolverAddress(_))
+        ));
+
+        assert!(matches!(
+            SudokuInput::<Fr>::read("{}".as_bytes()),
+            Err(InputError::Json(_))
+        ));
+    }
+}
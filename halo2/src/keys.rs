@@ -0,0 +1,639 @@
+//! Reading and writing KZG parameters and verifying keys for
+//! [`SudokuCircuit`](crate::SudokuCircuit) to and from disk, so that they don't need to be
+//! regenerated on every run.
+//!
+//! Proving keys can't be saved: halo2 has no serialization of proving keys at the version we
+//! depend on, and doesn't expose their parts. [`load_pk`] instead rebuilds the proving key from
+//! a verifying key file with `keygen_pk`, which skips committing to the fixed columns but
+//! still costs most of what generating the keys does. Saving proving keys needs a newer halo2.
+//!
+//! Every file starts with a fixed-size header:
+//!
+//! | bytes  | contents                                                        |
+//! | ------ | --------------------------------------------------------------- |
+//! | 0..8   | the magic bytes `SUDOKUH2`                                      |
+//! | 8..12  | format version, little-endian u32                               |
+//! | 12     | what the file holds: params or a verifying key                  |
+//! | 13     | the curve the file was generated over                           |
+//! | 14..18 | `k`, little-endian u32                                          |
+//! | 18..50 | fingerprint of the circuit's layout (zeroed for params)         |
+//! | 50..82 | fingerprint of the verifying key (zeroed for params)            |
+//!
+//! followed by the halo2 serialization of the parameters or key. Loading a key checks the
+//! header against the circuit and parameters it is being loaded for, so that a key generated
+//! for a different circuit is rejected instead of producing bad proofs. Both checks are cheap:
+//! the layout is recorded without computing any commitments, and the verifying key is hashed
+//! as loaded.
+
+use blake2b_simd::{Params as Blake2bParams, State as Blake2bState};
+use ff::PrimeField;
+use halo2wrong::halo2::{
+    circuit::Value,
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::{
+        keygen_pk, Advice, Any, Assigned, Assignment, Challenge, Circuit, Column, ConstraintSystem,
+        Error, Fixed, FloorPlanner, Instance, ProvingKey, Selector, VerifyingKey,
+    },
+    poly::{commitment::Params, kzg::commitment::ParamsKZG},
+    transcript::{
+        Blake2bWrite, Challenge255, EncodedChallenge, Transcript, TranscriptWriterBuffer,
+    },
+};
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+const MAGIC: &[u8; 8] = b"SUDOKUH2";
+
+/// Bumped whenever the layout of the header or of the data following it changes
+pub const FORMAT_VERSION: u32 = 2;
+
+// Identifies bn256, the only curve keys are currently written for
+const CURVE_BN256: u8 = 1;
+
+/// A 32-byte fingerprint of a circuit's layout or of a verifying key.
+pub type CircuitFingerprint = [u8; 32];
+
+/// What a key file holds. Proving keys can't be serialized, so there is no proving key kind.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileKind {
+    Params,
+    VerifyingKey,
+}
+
+impl FileKind {
+    fn to_byte(self) -> u8 {
+        match self {
+            FileKind::Params => 0,
+            FileKind::VerifyingKey => 1,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(FileKind::Params),
+            1 => Some(FileKind::VerifyingKey),
+            _ => None,
+        }
+    }
+}
+
+/// The reasons a parameters or key file can fail to be written or loaded.
+#[derive(Debug)]
+pub enum KeyFileError {
+    Io(io::Error),
+    /// The file doesn't start with the expected magic bytes
+    NotAKeyFile,
+    UnsupportedVersion(u32),
+    WrongKind {
+        expected: FileKind,
+        found: u8,
+    },
+    UnsupportedCurve(u8),
+    /// The file was generated for circuits of a different size than the given parameters
+    WrongK {
+        expected: u32,
+        found: u32,
+    },
+    /// The key was generated for a different circuit, or was corrupted since
+    CircuitMismatch {
+        expected: CircuitFingerprint,
+        found: CircuitFingerprint,
+    },
+    /// The circuit could not be laid out, or its proving key could not be rebuilt
+    Plonk(Error),
+}
+
+impl fmt::Display for KeyFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyFileError::Io(err) => write!(f, "{}", err),
+            KeyFileError::NotAKeyFile => write!(f, "not a sudoku parameters or key file"),
+            KeyFileError::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported file format version {} (expected {})",
+                version, FORMAT_VERSION
+            ),
+            KeyFileError::WrongKind { expected, found } => match FileKind::from_byte(*found) {
+                Some(found) => write!(
+                    f,
+                    "expected a {:?} file, found a {:?} file",
+                    expected, found
+                ),
+                None => write!(
+                    f,
+                    "expected a {:?} file, found unknown kind {}",
+                    expected, found
+                ),
+            },
+            KeyFileError::UnsupportedCurve(curve) => write!(f, "unsupported curve id {}", curve),
+            KeyFileError::WrongK { expected, found } => write!(
+                f,
+                "file was generated for k = {}, but the parameters have k = {}",
+                found, expected
+            ),
+            KeyFileError::CircuitMismatch { expected, found } => write!(
+                f,
+                "key was generated for a different circuit (fingerprint {}, expected {})",
+                hex(found),
+                hex(expected)
+            ),
+            KeyFileError::Plonk(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for KeyFileError {}
+
+impl From<io::Error> for KeyFileError {
+    fn from(err: io::Error) -> Self {
+        KeyFileError::Io(err)
+    }
+}
+
+impl From<Error> for KeyFileError {
+    fn from(err: Error) -> Self {
+        KeyFileError::Plonk(err)
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Computes the fingerprint of a verifying key. It covers the constraint system and the
+/// commitments to the fixed and permutation columns.
+pub fn vk_fingerprint(vk: &VerifyingKey<G1Affine>) -> CircuitFingerprint {
+    // The verifying key's transcript representation is what halo2 itself binds proofs to
+    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+    vk.hash_into(&mut transcript)
+        .expect("writing to a vec should not fail");
+    let challenge: Challenge255<G1Affine> = transcript.squeeze_challenge();
+    challenge.get_scalar().to_repr()
+}
+
+/// Computes the fingerprint of the layout of circuit `C`: the values of its fixed columns
+/// (including lookup tables), where its selectors are enabled and which cells it copies.
+/// Unlike the fingerprint of its verifying key, this takes no commitments to compute.
+pub fn layout_fingerprint<C: Circuit<Fr> + Default>() -> Result<CircuitFingerprint, Error> {
+    let mut cs = ConstraintSystem::default();
+    let config = C::configure(&mut cs);
+    let mut recorder = LayoutRecorder::default();
+    C::FloorPlanner::synthesize(
+        &mut recorder,
+        &C::default().without_witnesses(),
+        config,
+        cs.constants().clone(),
+    )?;
+
+    let mut fingerprint = [0; 32];
+    fingerprint.copy_from_slice(recorder.hash.finalize().as_bytes());
+    Ok(fingerprint)
+}
+
+// LayoutRecorder stands in for the prover during synthesis, hashing everything that ends up
+// in the fixed and permutation columns and ignoring the advice values. Synthesis is
+// deterministic, so the same circuit always produces the same hash
+struct LayoutRecorder {
+    hash: Blake2bState,
+    // selectors can't be told apart outside of halo2 other than by comparing them, so each
+    // one is numbered in the order it is first enabled
+    selectors: HashMap<Selector, u64>,
+}
+
+impl Default for LayoutRecorder {
+    fn default() -> Self {
+        LayoutRecorder {
+            hash: Blake2bParams::new()
+                .hash_length(32)
+                .personal(b"SudokuLayoutFgpt")
+                .to_state(),
+            selectors: HashMap::new(),
+        }
+    }
+}
+
+impl LayoutRecorder {
+    fn record(&mut self, tag: u8, numbers: &[u64]) {
+        self.hash.update(&[tag]);
+        for number in numbers {
+            self.hash.update(&number.to_le_bytes());
+        }
+    }
+
+    fn record_value(&mut self, value: Value<Assigned<Fr>>) {
+        let mut repr = [0; 32];
+        value.map(|value| repr = value.evaluate().to_repr());
+        self.hash.update(&repr);
+    }
+}
+
+// column_id identifies a column by its type and index
+fn column_id(column: Column<Any>) -> [u64; 2] {
+    let column_type = match column.column_type() {
+        Any::Advice(_) => 0,
+        Any::Fixed => 1,
+        Any::Instance => 2,
+    };
+    [column_type, column.index() as u64]
+}
+
+impl Assignment<Fr> for LayoutRecorder {
+    fn enter_region<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn exit_region(&mut self) {}
+
+    fn enable_selector<A, AR>(&mut self, _: A, selector: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let next_id = self.selectors.len() as u64;
+        let id = *self.selectors.entry(*selector).or_insert(next_id);
+        self.record(b's', &[id, row as u64]);
+        Ok(())
+    }
+
+    fn query_instance(&self, _: Column<Instance>, _: usize) -> Result<Value<Fr>, Error> {
+        Ok(Value::unknown())
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        _: Column<Advice>,
+        _: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<Fr>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Fixed>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<Fr>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.record(b'f', &[column.index() as u64, row as u64]);
+        self.record_value(to().map(Into::into));
+        Ok(())
+    }
+
+    fn copy(
+        &mut self,
+        left_column: Column<Any>,
+        left_row: usize,
+        right_column: Column<Any>,
+        right_row: usize,
+    ) -> Result<(), Error> {
+        let [left_type, left_index] = column_id(left_column);
+        let [right_type, right_index] = column_id(right_column);
+        self.record(
+            b'c',
+            &[
+                left_type,
+                left_index,
+                left_row as u64,
+                right_type,
+                right_index,
+                right_row as u64,
+            ],
+        );
+        Ok(())
+    }
+
+    fn fill_from_row(
+        &mut self,
+        column: Column<Fixed>,
+        row: usize,
+        to: Value<Assigned<Fr>>,
+    ) -> Result<(), Error> {
+        self.record(b'F', &[column.index() as u64, row as u64]);
+        self.record_value(to);
+        Ok(())
+    }
+
+    fn get_challenge(&self, _: Challenge) -> Value<Fr> {
+        Value::unknown()
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
+
+#[derive(Debug)]
+struct Header {
+    kind: FileKind,
+    k: u32,
+    layout_fingerprint: CircuitFingerprint,
+    vk_fingerprint: CircuitFingerprint,
+}
+
+impl Header {
+    fn write(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        writer.write_all(&[self.kind.to_byte(), CURVE_BN256])?;
+        writer.write_all(&self.k.to_le_bytes())?;
+        writer.write_all(&self.layout_fingerprint)?;
+        writer.write_all(&self.vk_fingerprint)
+    }
+
+    // read parses a header and checks it describes a file of the expected kind
+    fn read(reader: &mut impl Read, expected_kind: FileKind) -> Result<Self, KeyFileError> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if magic != *MAGIC {
+            return Err(KeyFileError::NotAKeyFile);
+        }
+
+        let version = read_u32(reader)?;
+        if version != FORMAT_VERSION {
+            return Err(KeyFileError::UnsupportedVersion(version));
+        }
+
+        let mut kind_and_curve = [0; 2];
+        reader.read_exact(&mut kind_and_curve)?;
+        let [kind, curve] = kind_and_curve;
+        if kind != expected_kind.to_byte() {
+            return Err(KeyFileError::WrongKind {
+                expected: expected_kind,
+                found: kind,
+            });
+        }
+        if curve != CURVE_BN256 {
+            return Err(KeyFileError::UnsupportedCurve(curve));
+        }
+
+        let k = read_u32(reader)?;
+        let mut layout_fingerprint = [0; 32];
+        reader.read_exact(&mut layout_fingerprint)?;
+        let mut vk_fingerprint = [0; 32];
+        reader.read_exact(&mut vk_fingerprint)?;
+
+        Ok(Header {
+            kind: expected_kind,
+            k,
+            layout_fingerprint,
+            vk_fingerprint,
+        })
+    }
+
+    // check_circuit rejects keys that were generated for different parameters or for a
+    // circuit laid out differently from `C`, before the key itself is read
+    fn check_circuit<C: Circuit<Fr> + Default>(
+        &self,
+        params: &ParamsKZG<Bn256>,
+    ) -> Result<(), KeyFileError> {
+        if self.k != params.k() {
+            return Err(KeyFileError::WrongK {
+                expected: params.k(),
+                found: self.k,
+            });
+        }
+
+        check_fingerprint(layout_fingerprint::<C>()?, self.layout_fingerprint)
+    }
+}
+
+fn check_fingerprint(
+    expected: CircuitFingerprint,
+    found: CircuitFingerprint,
+) -> Result<(), KeyFileError> {
+    if expected != found {
+        return Err(KeyFileError::CircuitMismatch { expected, found });
+    }
+    Ok(())
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+pub fn write_params(params: &ParamsKZG<Bn256>, writer: &mut impl Write) -> io::Result<()> {
+    let header = Header {
+        kind: FileKind::Params,
+        k: params.k(),
+        layout_fingerprint: [0; 32],
+        vk_fingerprint: [0; 32],
+    };
+    header.write(writer)?;
+    params.write(writer)
+}
+
+pub fn read_params(reader: &mut impl Read) -> Result<ParamsKZG<Bn256>, KeyFileError> {
+    let header = Header::read(reader, FileKind::Params)?;
+    let params = ParamsKZG::<Bn256>::read(reader)?;
+    if params.k() != header.k {
+        return Err(KeyFileError::WrongK {
+            expected: params.k(),
+            found: header.k,
+        });
+    }
+
+    Ok(params)
+}
+
+/// Writes a verifying key generated for circuit `C`
+pub fn write_vk<C: Circuit<Fr> + Default>(
+    vk: &VerifyingKey<G1Affine>,
+    params: &ParamsKZG<Bn256>,
+    writer: &mut impl Write,
+) -> Result<(), KeyFileError> {
+    let header = Header {
+        kind: FileKind::VerifyingKey,
+        k: params.k(),
+        layout_fingerprint: layout_fingerprint::<C>()?,
+        vk_fingerprint: vk_fingerprint(vk),
+    };
+    header.write(writer)?;
+    Ok(vk.write(writer)?)
+}
+
+/// Reads a verifying key, failing unless it was generated for circuit `C` and `params`
+pub fn read_vk<C: Circuit<Fr> + Default>(
+    reader: &mut impl Read,
+    params: &ParamsKZG<Bn256>,
+) -> Result<VerifyingKey<G1Affine>, KeyFileError> {
+    let header = Header::read(reader, FileKind::VerifyingKey)?;
+    header.check_circuit::<C>(params)?;
+
+    // The key is read with `C`'s constraint system, so a key for a circuit with other gates
+    // hashes differently from when it was written
+    let vk = VerifyingKey::read::<_, C>(reader, params)?;
+    check_fingerprint(header.vk_fingerprint, vk_fingerprint(&vk))?;
+    Ok(vk)
+}
+
+/// Reads a verifying key as [`read_vk`] does, and rebuilds the proving key from it. See the
+/// module documentation for why proving keys aren't read from disk.
+pub fn read_pk<C: Circuit<Fr> + Default>(
+    reader: &mut impl Read,
+    params: &ParamsKZG<Bn256>,
+) -> Result<ProvingKey<G1Affine>, KeyFileError> {
+    let vk = read_vk::<C>(reader, params)?;
+    let circuit = C::default().without_witnesses();
+    Ok(keygen_pk(params, vk, &circuit)?)
+}
+
+pub fn save_params(params: &ParamsKZG<Bn256>, path: impl AsRef<Path>) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_params(params, &mut writer)?;
+    writer.flush()
+}
+
+pub fn load_params(path: impl AsRef<Path>) -> Result<ParamsKZG<Bn256>, KeyFileError> {
+    read_params(&mut BufReader::new(File::open(path)?))
+}
+
+pub fn save_vk<C: Circuit<Fr> + Default>(
+    vk: &VerifyingKey<G1Affine>,
+    params: &ParamsKZG<Bn256>,
+    path: impl AsRef<Path>,
+) -> Result<(), KeyFileError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_vk::<C>(vk, params, &mut writer)?;
+    Ok(writer.flush()?)
+}
+
+pub fn load_vk<C: Circuit<Fr> + Default>(
+    path: impl AsRef<Path>,
+    params: &ParamsKZG<Bn256>,
+) -> Result<VerifyingKey<G1Affine>, KeyFileError> {
+    read_vk::<C>(&mut BufReader::new(File::open(path)?), params)
+}
+
+pub fn load_pk<C: Circuit<Fr> + Default>(
+    path: impl AsRef<Path>,
+    params: &ParamsKZG<Bn256>,
+) -> Result<ProvingKey<G1Affine>, KeyFileError> {
+    read_pk::<C>(&mut BufReader::new(File::open(path)?), params)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        fixtures, keygen, prove,
+        range::{LookupRangeCheck, PolynomialRangeCheck},
+        verify, CommitmentParams, SudokuCircuit, K,
+    };
+    use halo2wrong::halo2::plonk::keygen_vk;
+    use rand::rngs::OsRng;
+
+    type PolynomialCircuit = SudokuCircuit<Fr, 9, PolynomialRangeCheck>;
+    type LookupCircuit = SudokuCircuit<Fr, 9, LookupRangeCheck>;
+
+    #[test]
+    fn test_round_trip_keys() {
+        let params = ParamsKZG::<Bn256>::setup_params(K, OsRng);
+        let (_, vk) = keygen(&params).unwrap();
+
+        let mut params_bytes = vec![];
+        write_params(&params, &mut params_bytes).unwrap();
+        let mut vk_bytes = vec![];
+        write_vk::<PolynomialCircuit>(&vk, &params, &mut vk_bytes).unwrap();
+
+        let params = read_params(&mut params_bytes.as_slice()).unwrap();
+        let vk = read_vk::<PolynomialCircuit>(&mut vk_bytes.as_slice(), &params).unwrap();
+        let pk = read_pk::<PolynomialCircuit>(&mut vk_bytes.as_slice(), &params).unwrap();
+
+        let puzzle = fixtures::puzzle();
+        let solution = fixtures::solution();
+        let circuit = SudokuCircuit::new(puzzle.clone(), solution).unwrap();
+        let proof = prove(&params, &pk, circuit, OsRng).unwrap();
+        assert!(verify(&params, &vk, &puzzle, None, &proof).is_ok());
+    }
+
+    #[test]
+    fn test_reject_key_for_other_circuit() {
+        let params = ParamsKZG::<Bn256>::setup_params(K, OsRng);
+        let (_, vk) = keygen(&params).unwrap();
+
+        let mut vk_bytes = vec![];
+        write_vk::<PolynomialCircuit>(&vk, &params, &mut vk_bytes).unwrap();
+
+        assert_ne!(
+            layout_fingerprint::<PolynomialCircuit>().unwrap(),
+            layout_fingerprint::<LookupCircuit>().unwrap()
+        );
+        assert!(matches!(
+            read_vk::<LookupCircuit>(&mut vk_bytes.as_slice(), &params),
+            Err(KeyFileError::CircuitMismatch { .. })
+        ));
+        assert!(matches!(
+            read_params(&mut vk_bytes.as_slice()),
+            Err(KeyFileError::WrongKind { .. })
+        ));
+
+        let other_params = ParamsKZG::<Bn256>::setup_params(K + 1, OsRng);
+        assert!(matches!(
+            read_vk::<PolynomialCircuit>(&mut vk_bytes.as_slice(), &other_params),
+            Err(KeyFileError::WrongK { .. })
+        ));
+
+        let mut truncated = &vk_bytes[..4];
+        assert!(matches!(
+            read_vk::<PolynomialCircuit>(&mut truncated, &params),
+            Err(KeyFileError::Io(_))
+        ));
+        assert!(matches!(
+            read_vk::<PolynomialCircuit>(&mut &b"not a key file at all"[..], &params),
+            Err(KeyFileError::NotAKeyFile)
+        ));
+    }
+
+    #[test]
+    fn test_reject_key_for_other_tables() {
+        // In lookup mode the constraint system doesn't depend on the board size, so keys for
+        // these two circuits can only be told apart by their layout: their fixed columns and
+        // lookup tables
+        type SmallLookupCircuit = SudokuCircuit<Fr, 4, LookupRangeCheck>;
+
+        let params = ParamsKZG::<Bn256>::setup_params(K, OsRng);
+        let vk = keygen_vk(&params, &SmallLookupCircuit::default()).unwrap();
+
+        let mut vk_bytes = vec![];
+        write_vk::<SmallLookupCircuit>(&vk, &params, &mut vk_bytes).unwrap();
+
+        assert_ne!(
+            layout_fingerprint::<SmallLookupCircuit>().unwrap(),
+            layout_fingerprint::<LookupCircuit>().unwrap()
+        );
+        assert!(matches!(
+            read_vk::<LookupCircuit>(&mut vk_bytes.as_slice(), &params),
+            Err(KeyFileError::CircuitMismatch { .. })
+        ));
+        assert!(read_vk::<SmallLookupCircuit>(&mut vk_bytes.as_slice(), &params).is_ok());
+    }
+}
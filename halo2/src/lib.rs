@@ -1,9 +1,32 @@
+pub mod board;
+pub mod commitment;
+pub mod diagnostics;
+pub mod error;
+#[cfg(feature = "evm")]
+pub mod evm;
+#[cfg(test)]
+mod fixtures;
+pub mod generator;
+pub mod group;
+pub mod input;
+pub mod keys;
+pub mod prover;
+pub mod range;
+pub mod solver;
+pub mod stats;
 pub mod utilities;
 
-use halo2::{
-    plonk::{Column, Constraints, Expression, Instance, Selector},
-    poly::Rotation,
-};
+pub use board::{BoardError, Puzzle, Solution};
+pub use diagnostics::SudokuViolation;
+pub use error::SudokuError;
+pub use prover::{keygen, prove, setup, verify, CommitmentParams, VerifyError};
+pub use solver::SolveError;
+pub use stats::CircuitStats;
+
+use commitment::{poseidon_spec, PuzzleCells, PuzzleCommitmentKind, PuzzleCommitmentMode};
+use commitment::{POSEIDON_RATE, POSEIDON_T};
+use group::{GroupCheckConfig, GroupCheckMode, GroupCheckStrategy, PairwiseDistinct};
+use halo2::plonk::{Advice, Column, Instance, Selector};
 use halo2wrong::{
     halo2::{
         arithmetic::FieldExt,
@@ -12,62 +35,134 @@ use halo2wrong::{
     },
     RegionCtx,
 };
-use itertools::Itertools;
-use maingate::{MainGate, MainGateConfig, MainGateInstructions, Term};
+use maingate::{MainGate, MainGateConfig, MainGateInstructions};
 use ndarray::prelude::*;
+use range::{PolynomialRangeCheck, RangeCheckConfig, RangeCheckKind, RangeCheckMode};
 use std::marker::PhantomData;
-use utilities::range_check;
+use transcript::hasher::HasherChip;
+
+/// The number of rows in our 9x9 circuit cannot exceed 2^K.
+/// The 36 pairwise comparisons per row/column/square push us past 2^11 rows.
+/// For other board sizes, see `SudokuCircuit::stats`.
+pub const K: u32 = 12;
+
+/// The number of rows in our 9x9 circuit cannot exceed 2^K_HASHED_PUZZLE when the puzzle is
+/// exposed as a Poseidon hash (see `PuzzleCommitmentMode::PoseidonHash`). Hashing the 81 puzzle
+/// cells takes 21 permutations, which need far more rows than the sudoku checks themselves.
+/// Larger boards need more permutations, so for other board sizes, see `SudokuCircuit::stats`.
+pub const K_HASHED_PUZZLE: u32 = 15;
+
+// The names of the regions laid out by `SudokuCircuit::synthesize`, besides the ones named
+// after a cell or a group (see `diagnostics`), which `stats` sorts rows into phases by
+pub(crate) const LOAD_BOARDS_REGION: &str = "load boards";
+pub(crate) const HASH_PUZZLE_REGION: &str = "hash puzzle";
 
-const MAX_SUDOKU_CELL_VALUE: usize = 10;
+/// Arranges the public inputs for a proof about the given puzzle made by the given solver,
+/// one vector per instance column.
+/// `maingate` registers its own Instance column which we are not using, so that column's
+/// inputs are left empty. The puzzle and the solver id are loaded into columns of their own,
+/// with a missing solver id standing in as zero.
+pub fn public_inputs<F: FieldExt, const N: usize>(
+    puzzle: &Puzzle<N>,
+    solver_id: Option<F>,
+) -> Vec<Vec<F>> {
+    public_inputs_with_commitment(puzzle, solver_id, PuzzleCommitmentMode::Cells)
+}
+
+/// Arranges the public inputs like [`public_inputs`] does, for a circuit exposing the puzzle
+/// in the given mode. In `PuzzleCommitmentMode::PoseidonHash` the puzzle column holds only
+/// the digest computed by [`commitment::hash_puzzle`].
+pub fn public_inputs_with_commitment<F: FieldExt, const N: usize>(
+    puzzle: &Puzzle<N>,
+    solver_id: Option<F>,
+    mode: PuzzleCommitmentMode,
+) -> Vec<Vec<F>> {
+    let public_input_maingate = vec![];
+    let public_input_puzzle: Vec<F> = commitment::puzzle_public_inputs(puzzle, mode);
+    let public_input_solver = vec![solver_id.unwrap_or_else(F::zero)];
+
+    vec![
+        public_input_maingate,
+        public_input_puzzle,
+        public_input_solver,
+    ]
+}
 
 // SudokuConfig defines the columns we will use directly in our circuit,
 // as well as the configurations for all gadgets we will use. In this case,
 // we use the `maingate` gadget which is a convenience wrapper on top of the
 // standard PLONK gate and includes instructions for common primitives like `add`.
-// We also use a little range-checking gate built for constraining values within
-// small ranges (as opposed to a lookup-table-based range checking chip)
+// We also use a range-checking chip for constraining cells within small ranges,
+// built either as a little polynomial gate or on top of a lookup table (see `RangeCheckMode`)
 #[derive(Clone, Debug)]
 pub struct SudokuConfig {
     main_gate_config: MainGateConfig,
 
-    // used to expose the sudoku puzzle as a public input to the circuit
+    // used to expose the sudoku puzzle as a public input to the circuit: either all of its
+    // cells or only their hash, depending on `puzzle_commitment`
     public_input_puzzle: Column<Instance>,
+    puzzle_commitment: PuzzleCommitmentMode,
+
+    // used to expose the id of the solver the proof was made for as a public input, so that
+    // a proof seen by others (e.g. in a mempool) can't be resubmitted in another solver's name
+    public_input_solver: Column<Instance>,
+
+    // holds every cell of the puzzle and of the solution, one per row. This is the column
+    // the range checks look at, and it is owned by us rather than by `maingate` so that
+    // its layout can't change underneath us
+    board: Column<Advice>,
 
-    // used to toggle the range-checking gate on for the rows containing board inputs
-    range_check_selector: Selector,
+    // used to toggle the puzzle and solution range checks on for the rows containing board inputs
+    range_check_config: RangeCheckConfig,
+
+    // used to check that each row, column and box of the solution holds every digit once
+    group_check_config: GroupCheckConfig,
 }
 
 impl SudokuConfig {
-    pub fn new<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+    // new configures the circuit for boards of size `board_size`
+    pub fn new<F: FieldExt>(
+        meta: &mut ConstraintSystem<F>,
+        board_size: usize,
+        range_check_mode: RangeCheckMode,
+        puzzle_commitment: PuzzleCommitmentMode,
+        group_check_mode: GroupCheckMode,
+    ) -> Self {
         let main_gate_config = MainGate::configure(meta);
         let puzzle = meta.instance_column();
         meta.enable_equality(puzzle);
+        let solver = meta.instance_column();
+        meta.enable_equality(solver);
 
-        // enable the range-check gate
-        let range_check_selector = meta.selector();
-        meta.create_gate("range check", |meta| {
-            let selector = meta.query_selector(range_check_selector);
-
-            // This little range-check gate needs to know which advice column it's looking at
-            // for the cells whose values it will constrain. We exploit the fact that we know
-            // (from reading maingate's code) that maingate's `assign_value()` function always
-            // loads the given value into the first of its five advice columns. This is admittedly
-            // fragile and breaks the abstraction barrier of `maingate`.
-            let input_column = main_gate_config.advices()[0];
-            let input_column: Expression<F> = meta.query_advice(input_column, Rotation::cur());
-
-            let range_check_constraint = Some(range_check(input_column, MAX_SUDOKU_CELL_VALUE));
-
-            Constraints::with_selector(selector, range_check_constraint)
-        });
+        // The board cells are range checked in place in their own column, and are then
+        // copied into maingate's columns (with copy constraints) by whichever maingate
+        // instruction consumes them, so equality must be enabled here
+        let board = meta.advice_column();
+        meta.enable_equality(board);
+        let range_check_config =
+            RangeCheckConfig::configure(meta, board, range_check_mode, board_size as u64);
+        let group_check_config = GroupCheckConfig::configure(
+            meta,
+            main_gate_config.clone(),
+            board_size,
+            group_check_mode,
+        );
 
         SudokuConfig {
             main_gate_config,
             public_input_puzzle: puzzle,
-            range_check_selector,
+            puzzle_commitment,
+            public_input_solver: solver,
+            board,
+            range_check_config,
+            group_check_config,
         }
     }
 
+    pub fn group_check_mode(&self) -> GroupCheckMode {
+        self.group_check_config.mode()
+    }
+
     fn main_gate<F: FieldExt>(&self) -> MainGate<F> {
         MainGate::<F>::new(self.main_gate_config.clone())
     }
@@ -75,23 +170,63 @@ impl SudokuConfig {
 
 // SudokuCircuit is responsible for initializing its config (and all gadgets registered therein)
 // as well as defining all the constraints for the table (i.e. constructing the circuit)
-#[derive(Clone, Debug, Default)]
-pub struct SudokuCircuit<F: FieldExt> {
-    pub puzzle: Array2<u8>,
-    pub solution: Array2<u8>,
-    marker: PhantomData<F>,
+// The board is `N` by `N` cells, split into boxes of `sqrt(N)` by `sqrt(N)` cells. The `R`
+// parameter picks the range check used for board cells, `C` picks how the puzzle is
+// exposed to the verifier, and `G` picks how each row, column and box is checked
+// Circuits can only be built with `SudokuCircuit::new`, which checks that the solution
+// agrees with the puzzle
+#[derive(Clone, Debug)]
+pub struct SudokuCircuit<
+    F: FieldExt,
+    const N: usize = 9,
+    R: RangeCheckKind = PolynomialRangeCheck,
+    C: PuzzleCommitmentKind = PuzzleCells,
+    G: GroupCheckStrategy = PairwiseDistinct,
+> {
+    puzzle: Puzzle<N>,
+    solution: Solution<N>,
+    // the solver this proof is bound to, if any. Exposed as a public input either way
+    solver_id: Option<F>,
+    // the prover's hints for `GroupCheckMode::IndexHint`, one per group in the order of
+    // `SudokuCircuit::groups`. Generated from the solution unless overridden
+    digit_positions: Option<Vec<Vec<usize>>>,
+    marker: PhantomData<(F, R, C, G)>,
+}
+
+// The default circuit has blank boards, and is only good for key generation
+impl<F, const N: usize, R, C, G> Default for SudokuCircuit<F, N, R, C, G>
+where
+    F: FieldExt,
+    R: RangeCheckKind,
+    C: PuzzleCommitmentKind,
+    G: GroupCheckStrategy,
+{
+    fn default() -> Self {
+        Self::new_unchecked(
+            Puzzle::blank(),
+            Solution::new_unchecked(Array2::zeros((N, N))),
+        )
+    }
 }
 
-impl<F: FieldExt> Circuit<F> for SudokuCircuit<F> {
+impl<F, const N: usize, R, C, G> Circuit<F> for SudokuCircuit<F, N, R, C, G>
+where
+    F: FieldExt,
+    R: RangeCheckKind,
+    C: PuzzleCommitmentKind,
+    G: GroupCheckStrategy,
+{
     type Config = SudokuConfig;
     type FloorPlanner = SimpleFloorPlanner;
 
+    // The board values are never read during key generation, but synthesis still needs
+    // boards of the right shape to lay the circuit out
     fn without_witnesses(&self) -> Self {
         Self::default()
     }
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-        SudokuConfig::new(meta)
+        SudokuConfig::new(meta, N, R::MODE, C::MODE, G::MODE)
     }
 
     fn synthesize(
@@ -101,9 +236,14 @@ impl<F: FieldExt> Circuit<F> for SudokuCircuit<F> {
     ) -> Result<(), Error> {
         let main_gate: MainGate<F> = config.main_gate();
         let mut puzzle_cells: Array2<AssignedCell<F, F>> = array![[]];
+        let mut solution_cells: Array2<AssignedCell<F, F>> = array![[]];
+        let mut solver_cell: Option<AssignedCell<F, F>> = None;
+        let mut puzzle_digest: Option<AssignedCell<F, F>> = None;
+
+        config.range_check_config.load_tables(&mut layouter)?;
 
         layouter.assign_region(
-            || "region 0",
+            || LOAD_BOARDS_REGION,
             |region| {
                 let offset = 0;
                 let ctx = &mut RegionCtx::new(region, offset);
@@ -112,119 +252,281 @@ impl<F: FieldExt> Circuit<F> for SudokuCircuit<F> {
                 // Note that this is loaded into advice columns as is the solution.
                 // Later we will compare all cells for the puzzle against all cells
                 // in our public_input column to effectively expose this as a public input.
-                puzzle_cells = self.load_board(&config, ctx, &self.puzzle)?;
+                puzzle_cells = self.load_board(
+                    &config,
+                    ctx,
+                    "puzzle",
+                    self.puzzle.cells(),
+                    config.range_check_config.puzzle_selector(),
+                )?;
 
                 // load the solution (private) into the circuit
-                let solution_cells = self.load_board(&config, ctx, &self.solution)?;
-
-                // check that both the rows and the columns are valid
-                for i in 0..9 {
-                    let row = solution_cells.row(i);
-                    let col = solution_cells.column(i);
-                    self.check_nine_cells(&config, ctx, row)?;
-                    self.check_nine_cells(&config, ctx, col)?;
-                }
-
-                // check each 3x3 square
-                for sq_start_row in [0, 3, 6] {
-                    for sq_start_col in [0, 3, 6] {
-                        let sq_end_row = sq_start_row + 3;
-                        let sq_end_col = sq_start_col + 3;
-                        let square = solution_cells
-                            .slice(s![sq_start_row..sq_end_row, sq_start_col..sq_end_col]);
-                        self.check_nine_cells(&config, ctx, square)?;
-                    }
-                }
+                solution_cells = self.load_board(
+                    &config,
+                    ctx,
+                    "solution",
+                    self.solution.cells(),
+                    config.range_check_config.solution_selector(),
+                )?;
 
-                // check that the solution matches the board
-                // check that each cell in `board` is either zero or is equal to the corresponding cell in `solution`
-                for row_idx in 0..9 {
-                    for col_idx in 0..9 {
-                        let board_cell = &puzzle_cells[[row_idx, col_idx]];
-                        let solution_cell = &solution_cells[[row_idx, col_idx]];
-
-                        // query if puzzle cell is zero
-                        let board_cell_is_zero = main_gate.is_zero(ctx, &board_cell)?;
-
-                        // query if puzzle cell equals solution cell
-                        let board_cell_equals_solution =
-                            main_gate.is_equal(ctx, board_cell, solution_cell)?;
-
-                        // assert at least one of the two expressions above is true
-                        main_gate.one_or_one(
-                            ctx,
-                            &board_cell_is_zero,
-                            &board_cell_equals_solution,
-                        )?;
-                    }
-                }
+                // load the solver id (public) into the circuit. Like the puzzle, it is
+                // exposed as a public input once the region is laid out below
+                let solver_id = Value::known(self.solver_id.unwrap_or_else(F::zero));
+                solver_cell = Some(main_gate.assign_value(ctx, solver_id)?);
                 Ok(())
             },
         )?;
 
-        // mark each cell of the puzzle as public input
-        for (public_input_idx, assigned_value) in puzzle_cells.iter().enumerate() {
-            layouter.constrain_instance(
-                assigned_value.cell(),
-                config.public_input_puzzle,
-                public_input_idx,
+        // check that the solution matches the board
+        // check that each cell in `board` is either zero or is equal to the corresponding cell in `solution`.
+        // Each cell gets a region of its own, so that a failure points at the cell
+        for ((row_idx, col_idx), board_cell) in puzzle_cells.indexed_iter() {
+            let solution_cell = &solution_cells[[row_idx, col_idx]];
+            layouter.assign_region(
+                || diagnostics::cell_region_name(row_idx, col_idx),
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+
+                    // query if puzzle cell is zero
+                    let board_cell_is_zero = main_gate.is_zero(ctx, board_cell)?;
+
+                    // query if puzzle cell equals solution cell
+                    let board_cell_equals_solution =
+                        main_gate.is_equal(ctx, board_cell, solution_cell)?;
+
+                    // assert at least one of the two expressions above is true
+                    main_gate.one_or_one(ctx, &board_cell_is_zero, &board_cell_equals_solution)
+                },
             )?;
         }
 
+        // hash the puzzle cells, in the same row-major order as the native
+        // `commitment::hash_puzzle`, so that only the digest needs to be public
+        if config.puzzle_commitment == PuzzleCommitmentMode::PoseidonHash {
+            puzzle_digest = Some(layouter.assign_region(
+                || HASH_PUZZLE_REGION,
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    self.hash_puzzle_cells(&config, ctx, &puzzle_cells)
+                },
+            )?);
+        }
+
+        // check that every row, column and box is valid, each in a region named after it
+        let group_names: Vec<String> = diagnostics::Group::all::<N>()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        config.group_check_config.check_groups(
+            &mut layouter,
+            &group_names,
+            &Self::groups(&solution_cells),
+            &self.digit_positions(),
+        )?;
+
+        match puzzle_digest {
+            // mark the puzzle's digest as the only public input for the puzzle
+            Some(puzzle_digest) => {
+                layouter.constrain_instance(puzzle_digest.cell(), config.public_input_puzzle, 0)?;
+            }
+            // mark each cell of the puzzle as public input
+            None => {
+                for (public_input_idx, assigned_value) in puzzle_cells.iter().enumerate() {
+                    layouter.constrain_instance(
+                        assigned_value.cell(),
+                        config.public_input_puzzle,
+                        public_input_idx,
+                    )?;
+                }
+            }
+        }
+
+        // mark the solver id as public input. The copy constraint to the instance column is
+        // what binds the proof to this solver: verifying it against any other id fails
+        if let Some(solver_cell) = solver_cell {
+            layouter.constrain_instance(solver_cell.cell(), config.public_input_solver, 0)?;
+        }
+
         Ok(())
     }
 }
 
-impl<F: FieldExt> SudokuCircuit<F> {
-    // load_board loads 81 values for either the puzzle or the solution into a single
-    // advice column, and turns on the [0, 10) range-checking gate for each row populated
-    // this way
+// isqrt returns the integer square root of `n`, rounded down
+pub(crate) const fn isqrt(n: usize) -> usize {
+    let mut root = 0;
+    while (root + 1) * (root + 1) <= n {
+        root += 1;
+    }
+    root
+}
+
+impl<F, const N: usize, R, C, G> SudokuCircuit<F, N, R, C, G>
+where
+    F: FieldExt,
+    R: RangeCheckKind,
+    C: PuzzleCommitmentKind,
+    G: GroupCheckStrategy,
+{
+    /// The width and height of each box of the board
+    pub const BOX_SIZE: usize = {
+        let box_size = isqrt(N);
+        assert!(
+            box_size * box_size == N,
+            "the board size must be a perfect square"
+        );
+        box_size
+    };
+
+    /// Builds a circuit proving knowledge of `solution` to `puzzle`, failing if the two
+    /// disagree on any clue.
+    pub fn new(puzzle: Puzzle<N>, solution: Solution<N>) -> Result<Self, BoardError> {
+        solution.check_matches(&puzzle)?;
+        Ok(Self::new_unchecked(puzzle, solution))
+    }
+
+    /// Builds a circuit proving knowledge of the solution to `puzzle`, found with the native
+    /// solver. Fails unless the puzzle has exactly one solution.
+    pub fn from_puzzle_solving(puzzle: Puzzle<N>) -> Result<Self, SolveError> {
+        let solution = solver::solve(&puzzle)?;
+        Ok(Self::new_unchecked(puzzle, solution))
+    }
+
+    // see `Puzzle::new_unchecked`
+    pub(crate) fn new_unchecked(puzzle: Puzzle<N>, solution: Solution<N>) -> Self {
+        SudokuCircuit {
+            puzzle,
+            solution,
+            solver_id: None,
+            digit_positions: None,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn puzzle(&self) -> &Puzzle<N> {
+        &self.puzzle
+    }
+
+    pub fn solution(&self) -> &Solution<N> {
+        &self.solution
+    }
+
+    pub fn solver_id(&self) -> Option<F> {
+        self.solver_id
+    }
+
+    // with_solver_id binds the proof to the given solver
+    pub fn with_solver_id(mut self, solver_id: F) -> Self {
+        self.solver_id = Some(solver_id);
+        self
+    }
+
+    // with_digit_positions replaces the hints generated from the solution for
+    // `GroupCheckMode::IndexHint`, e.g. to check that the circuit rejects bad ones
+    pub fn with_digit_positions(mut self, digit_positions: Vec<Vec<usize>>) -> Self {
+        self.digit_positions = Some(digit_positions);
+        self
+    }
+
+    /// Splits a board into its groups: the N rows, then the N columns, then the N boxes
+    /// in row-major order.
+    pub fn groups<T: Clone>(board: &Array2<T>) -> Vec<Vec<T>> {
+        let rows = (0..N).map(|i| board.row(i).to_vec());
+        let cols = (0..N).map(|i| board.column(i).to_vec());
+        let boxes = (0..N).map(|i| {
+            let sq_start_row = i / Self::BOX_SIZE * Self::BOX_SIZE;
+            let sq_start_col = i % Self::BOX_SIZE * Self::BOX_SIZE;
+            let sq_end_row = sq_start_row + Self::BOX_SIZE;
+            let sq_end_col = sq_start_col + Self::BOX_SIZE;
+            board
+                .slice(s![sq_start_row..sq_end_row, sq_start_col..sq_end_col])
+                .iter()
+                .cloned()
+                .collect()
+        });
+
+        rows.chain(cols).chain(boxes).collect()
+    }
+
+    /// Returns the prover's hints for `GroupCheckMode::IndexHint`: for each group (in the
+    /// order of [`Self::groups`]), the index of each digit within it. Unless overridden with
+    /// `with_digit_positions`, these are generated natively from the solution.
+    pub fn digit_positions(&self) -> Vec<Vec<usize>> {
+        match &self.digit_positions {
+            Some(digit_positions) => digit_positions.clone(),
+            None => Self::groups(self.solution.cells())
+                .iter()
+                .map(|group| group::digit_positions(group))
+                .collect(),
+        }
+    }
+
+    /// Returns the smallest `k` such that this circuit fits in 2^k rows, as measured by
+    /// [`Self::stats`].
+    pub fn min_k(&self) -> Result<u32, Error> {
+        Ok(self.stats()?.min_k)
+    }
+
+    /// Reports how many rows each phase of synthesis uses, along with the circuit's columns,
+    /// degree and smallest `k` (see [`CircuitStats`]).
+    pub fn stats(&self) -> Result<CircuitStats, Error> {
+        CircuitStats::measure(self)
+    }
+
+    /// Runs the circuit through the mock prover against its own public inputs, and explains
+    /// every failure in terms of the board, e.g. a digit appearing twice in a row (see
+    /// [`diagnostics`]).
+    pub fn diagnose(&self) -> Result<Vec<SudokuViolation>, Error> {
+        let instances = public_inputs_with_commitment(&self.puzzle, self.solver_id, C::MODE);
+        diagnostics::diagnose(self, instances)
+    }
+
+    // load_board loads the N*N values for either the puzzle or the solution into the board
+    // advice column, and turns on the given range-checking gate for each row populated
+    // this way. The returned cells are the range-checked cells themselves; every later
+    // constraint copies them into maingate's columns, so the two can never drift apart.
+    // Each cell is annotated with `board_name` and its position, e.g. "puzzle cell (0,0)"
     fn load_board(
         &self,
         config: &SudokuConfig,
         ctx: &mut RegionCtx<F>,
+        board_name: &str,
         board: &Array2<u8>,
+        range_check_selector: Selector,
     ) -> Result<Array2<AssignedCell<F, F>>, Error> {
-        let main_gate: MainGate<F> = config.main_gate();
-
-        let loaded_cells: Array2<AssignedCell<F, F>> = board.mapv(|value| {
+        let mut loaded_cells = Vec::with_capacity(board.len());
+        for ((row, col), &value) in board.indexed_iter() {
             // turn on the range-checking gate for this row
-            ctx.enable(config.range_check_selector).unwrap();
+            ctx.enable(range_check_selector)?;
 
             let value = Value::known(F::from_u128(u128::from(value)));
-            main_gate.assign_value(ctx, value).unwrap()
-        });
+            let annotation = || format!("{} cell ({},{})", board_name, row, col);
+            loaded_cells.push(ctx.assign_advice(annotation, config.board, value)?);
+            ctx.next();
+        }
 
-        Ok(loaded_cells)
+        // the cells were loaded in the board's logical (row-major) order
+        Ok(Array2::from_shape_vec(board.raw_dim(), loaded_cells)
+            .expect("one cell is loaded per board cell"))
     }
 
-    // check_nine_cells assumes all values in the given cells are within [0, 10),
-    // and will check that all values are unique and sum to 45
-    fn check_nine_cells<'a, I>(
+    // hash_puzzle_cells computes the Poseidon hash of the given (already range-checked)
+    // puzzle cells in-circuit, returning the cell holding the digest
+    fn hash_puzzle_cells(
         &self,
         config: &SudokuConfig,
         ctx: &mut RegionCtx<F>,
-        cells: I,
-    ) -> Result<(), Error>
-    where
-        I: IntoIterator<Item = &'a AssignedCell<F, F>>,
-    {
-        let main_gate: MainGate<F> = config.main_gate();
-
-        let cells: Vec<&AssignedCell<F, F>> = cells.into_iter().collect();
-
-        // Check sum of cells == 45
-        // Do this by checking that the sum of the cells plus (-45) equals zero
-        let expected_sum = F::from(45);
-        let terms: Vec<Term<F>> = cells.iter().cloned().map(Term::assigned_to_add).collect();
-        main_gate.assert_zero_sum(ctx, &terms, expected_sum.neg())?;
-
-        // Now check that all cells are unique by asserting each adjacent pair is non-equal
-        for (cell_a, cell_b) in cells.into_iter().tuple_windows() {
-            main_gate.assert_not_equal(ctx, &cell_a, &cell_b)?;
-        }
-
-        Ok(())
+        puzzle_cells: &Array2<AssignedCell<F, F>>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        // The limb parameters of `HasherChip` are only used when absorbing non-native
+        // field elements, which we never do
+        let mut hasher = HasherChip::<F, 4, 68, POSEIDON_T, POSEIDON_RATE>::new(
+            ctx,
+            &poseidon_spec(),
+            &config.main_gate_config,
+        )?;
+        let cells: Vec<AssignedCell<F, F>> = puzzle_cells.iter().cloned().collect();
+        hasher.update(&cells);
+        hasher.hash(ctx)
     }
 }
 #[cfg(test)]
@@ -233,183 +535,128 @@ mod test {
 
     use super::*;
 
+    // unchecked_circuit builds a circuit around the given boards as they are, so that the
+    // circuit's constraints can be tested against boards `SudokuCircuit::new` would reject
+    fn unchecked_circuit<F, const N: usize, R, C, G>(
+        puzzle: Array2<u8>,
+        solution: Array2<u8>,
+    ) -> SudokuCircuit<F, N, R, C, G>
+    where
+        F: FieldExt,
+        R: RangeCheckKind,
+        C: PuzzleCommitmentKind,
+        G: GroupCheckStrategy,
+    {
+        SudokuCircuit::new_unchecked(
+            Puzzle::new_unchecked(puzzle),
+            Solution::new_unchecked(solution),
+        )
+    }
+
     fn prove_and_verify_circuit(
         puzzle: Array2<u8>,
         solution: Array2<u8>,
     ) -> Result<(), Vec<VerifyFailure>> {
-        use halo2wrong::halo2::{dev::MockProver, halo2curves::bn256::Fr as Fp};
+        prove_and_verify_circuit_with_range_check::<PolynomialRangeCheck>(puzzle, solution)
+    }
 
-        // The number of rows in our circuit cannot exceed 2^k.
-        let k = 11;
+    fn prove_and_verify_circuit_with_range_check<R: RangeCheckKind>(
+        puzzle: Array2<u8>,
+        solution: Array2<u8>,
+    ) -> Result<(), Vec<VerifyFailure>> {
+        use halo2wrong::halo2::{dev::MockProver, halo2curves::bn256::Fr as Fp};
 
         // Instantiate the circuit with its inputs
-        let circuit = SudokuCircuit {
-            puzzle,
-            solution,
-            marker: PhantomData,
-        };
+        let circuit: SudokuCircuit<Fp, 9, R> = unchecked_circuit(puzzle, solution);
 
         // Arrange the public inputs.
-        // `maingate` registers its own Instance column which we are not using, so that column's
-        // inputs can be skipped.
-        // The puzzle is loaded into our own column
-        let public_input_maingate = vec![];
-        let public_input_puzzle: Vec<Fp> = circuit
-            .puzzle
-            .mapv(|value| Fp::from_u128(u128::from(value)))
-            .into_raw_vec();
-
-        let public_inputs = vec![public_input_maingate, public_input_puzzle];
+        let public_inputs = public_inputs(&circuit.puzzle, circuit.solver_id);
 
         // Run prover and return result of attempting to verify
-        let prover = MockProver::run(k, &circuit, public_inputs).unwrap();
+        let prover = MockProver::run(K, &circuit, public_inputs).unwrap();
 
         prover.verify()
     }
 
     #[test]
     fn test_happy_path() {
-        let puzzle = array![
-            [0, 0, 0, 2, 6, 0, 7, 0, 1],
-            [6, 8, 0, 0, 7, 0, 0, 9, 0],
-            [1, 9, 0, 0, 0, 4, 5, 0, 0],
-            [8, 2, 0, 1, 0, 0, 0, 4, 0],
-            [0, 0, 4, 6, 0, 2, 9, 0, 0],
-            [0, 5, 0, 0, 0, 3, 0, 2, 8],
-            [0, 0, 9, 3, 0, 0, 0, 7, 4],
-            [0, 4, 0, 0, 5, 0, 0, 3, 6],
-            [7, 0, 3, 0, 1, 8, 0, 0, 0],
-        ];
-        let solution = array![
-            [4, 3, 5, 2, 6, 9, 7, 8, 1],
-            [6, 8, 2, 5, 7, 1, 4, 9, 3],
-            [1, 9, 7, 8, 3, 4, 5, 6, 2],
-            [8, 2, 6, 1, 9, 5, 3, 4, 7],
-            [3, 7, 4, 6, 8, 2, 9, 1, 5],
-            [9, 5, 1, 7, 4, 3, 6, 2, 8],
-            [5, 1, 9, 3, 2, 6, 8, 7, 4],
-            [2, 4, 8, 9, 5, 7, 1, 3, 6],
-            [7, 6, 3, 4, 1, 8, 2, 5, 9],
-        ];
+        let puzzle = fixtures::puzzle_cells();
+        let solution = fixtures::solution_cells();
         let result = prove_and_verify_circuit(puzzle, solution);
         assert_eq!(result, Ok(()));
     }
 
     #[test]
     fn test_incorrect_solution() {
-        let puzzle = array![
-            [0, 0, 0, 2, 6, 0, 7, 0, 1],
-            [6, 8, 0, 0, 7, 0, 0, 9, 0],
-            [1, 9, 0, 0, 0, 4, 5, 0, 0],
-            [8, 2, 0, 1, 0, 0, 0, 4, 0],
-            [0, 0, 4, 6, 0, 2, 9, 0, 0],
-            [0, 5, 0, 0, 0, 3, 0, 2, 8],
-            [0, 0, 9, 3, 0, 0, 0, 7, 4],
-            [0, 4, 0, 0, 5, 0, 0, 3, 6],
-            [7, 0, 3, 0, 1, 8, 0, 0, 0],
-        ];
-        let solution = array![
-            [1, 3, 5, 2, 6, 9, 7, 8, 1], // top-left cell changed from 4 to 1
-            [6, 8, 2, 5, 7, 1, 4, 9, 3],
-            [1, 9, 7, 8, 3, 4, 5, 6, 2],
-            [8, 2, 6, 1, 9, 5, 3, 4, 7],
-            [3, 7, 4, 6, 8, 2, 9, 1, 5],
-            [9, 5, 1, 7, 4, 3, 6, 2, 8],
-            [5, 1, 9, 3, 2, 6, 8, 7, 4],
-            [2, 4, 8, 9, 5, 7, 1, 3, 6],
-            [7, 6, 3, 4, 1, 8, 2, 5, 9],
-        ];
+        let puzzle = fixtures::puzzle_cells();
+        let mut solution = fixtures::solution_cells();
+        solution[[0, 0]] = 1; // top-left cell changed from 4 to 1
         let result = prove_and_verify_circuit(puzzle, solution);
         assert!(result.is_err());
     }
 
     #[test]
     fn test_solution_cell_out_of_range() {
-        let puzzle = array![
-            [0, 0, 0, 2, 6, 0, 7, 0, 1],
-            [6, 8, 0, 0, 7, 0, 0, 9, 0],
-            [1, 9, 0, 0, 0, 4, 5, 0, 0],
-            [8, 2, 0, 1, 0, 0, 0, 4, 0],
-            [0, 0, 4, 6, 0, 2, 9, 0, 0],
-            [0, 5, 0, 0, 0, 3, 0, 2, 8],
-            [0, 0, 9, 3, 0, 0, 0, 7, 4],
-            [0, 4, 0, 0, 5, 0, 0, 3, 6],
-            [7, 0, 3, 0, 1, 8, 0, 0, 0],
-        ];
-        let solution = array![
-            [10, 3, 5, 2, 6, 9, 7, 8, 1],
-            [6, 8, 2, 5, 7, 1, 4, 9, 3],
-            [1, 9, 7, 8, 3, 4, 5, 6, 2],
-            [8, 2, 6, 1, 9, 5, 3, 4, 7],
-            [3, 7, 4, 6, 8, 2, 9, 1, 5],
-            [9, 5, 1, 7, 4, 3, 6, 2, 8],
-            [5, 1, 9, 3, 2, 6, 8, 7, 4],
-            [2, 4, 8, 9, 5, 7, 1, 3, 6],
-            [7, 6, 3, 4, 1, 8, 2, 5, 9],
-        ];
+        let puzzle = fixtures::puzzle_cells();
+        let mut solution = fixtures::solution_cells();
+        solution[[0, 0]] = 10; // top-left cell changed from 4 to 10, out of range
         let result = prove_and_verify_circuit(puzzle, solution);
         assert!(result.is_err());
     }
 
     #[test]
-    #[should_panic]
     fn test_solution_wrong_shape() {
-        let puzzle = array![
-            [0, 0, 0, 2, 6, 0, 7, 0, 1],
-            [6, 8, 0, 0, 7, 0, 0, 9, 0],
-            [1, 9, 0, 0, 0, 4, 5, 0, 0],
-            [8, 2, 0, 1, 0, 0, 0, 4, 0],
-            [0, 0, 4, 6, 0, 2, 9, 0, 0],
-            [0, 5, 0, 0, 0, 3, 0, 2, 8],
-            [0, 0, 9, 3, 0, 0, 0, 7, 4],
-            [0, 4, 0, 0, 5, 0, 0, 3, 6],
-            [7, 0, 3, 0, 1, 8, 0, 0, 0],
-        ];
         let solution = array![
             [1, 3, 5, 2, 6, 9, 7, 8, 1],
             [6, 8, 2, 5, 7, 1, 4, 9, 3],
             [1, 9, 7, 8, 3, 4, 5, 6, 2],
             [8, 2, 6, 1, 9, 5, 3, 4, 7],
         ];
-        let result = prove_and_verify_circuit(puzzle, solution);
-        assert!(result.is_err());
+        assert_eq!(
+            Solution::<9>::new(solution),
+            Err(BoardError::WrongShape {
+                expected: 9,
+                found: vec![4, 9]
+            })
+        );
+    }
+
+    #[test]
+    fn test_new_rejects_solution_not_matching_puzzle() {
+        use halo2wrong::halo2::halo2curves::bn256::Fr as Fp;
+
+        let puzzle = fixtures::puzzle();
+
+        // a valid solution, but to a different puzzle
+        let solution = Solution::new(pattern_solution::<9>()).unwrap();
+        let result: Result<SudokuCircuit<Fp>, _> = SudokuCircuit::new(puzzle.clone(), solution);
+        assert_eq!(
+            result.unwrap_err(),
+            BoardError::Mismatch {
+                row: 0,
+                col: 3,
+                puzzle: 2,
+                solution: 4
+            }
+        );
+
+        let solution = fixtures::solution();
+        let result: Result<SudokuCircuit<Fp>, _> = SudokuCircuit::new(puzzle, solution);
+        assert!(result.is_ok());
     }
 
     #[test]
     fn test_valid_solution_but_wrong_public_input() {
         use halo2wrong::halo2::{dev::MockProver, halo2curves::bn256::Fr as Fp};
-        let puzzle = array![
-            [0, 0, 0, 2, 6, 0, 7, 0, 1],
-            [6, 8, 0, 0, 7, 0, 0, 9, 0],
-            [1, 9, 0, 0, 0, 4, 5, 0, 0],
-            [8, 2, 0, 1, 0, 0, 0, 4, 0],
-            [0, 0, 4, 6, 0, 2, 9, 0, 0],
-            [0, 5, 0, 0, 0, 3, 0, 2, 8],
-            [0, 0, 9, 3, 0, 0, 0, 7, 4],
-            [0, 4, 0, 0, 5, 0, 0, 3, 6],
-            [7, 0, 3, 0, 1, 8, 0, 0, 0],
-        ];
-        let solution = array![
-            [4, 3, 5, 2, 6, 9, 7, 8, 1],
-            [6, 8, 2, 5, 7, 1, 4, 9, 3],
-            [1, 9, 7, 8, 3, 4, 5, 6, 2],
-            [8, 2, 6, 1, 9, 5, 3, 4, 7],
-            [3, 7, 4, 6, 8, 2, 9, 1, 5],
-            [9, 5, 1, 7, 4, 3, 6, 2, 8],
-            [5, 1, 9, 3, 2, 6, 8, 7, 4],
-            [2, 4, 8, 9, 5, 7, 1, 3, 6],
-            [7, 6, 3, 4, 1, 8, 2, 5, 9],
-        ];
-        let k = 11;
-        let circuit = SudokuCircuit {
-            puzzle,
-            solution,
-            marker: PhantomData,
-        };
+        let puzzle = fixtures::puzzle_cells();
+        let solution = fixtures::solution_cells();
+        let k = K;
+        let circuit: SudokuCircuit<Fp> = unchecked_circuit(puzzle, solution);
         // Arrange the public inputs (correctly)
         let public_input_maingate = vec![];
         let mut public_input_puzzle: Vec<Fp> = circuit
             .puzzle
+            .cells()
             .mapv(|value| Fp::from_u128(u128::from(value)))
             .into_raw_vec();
 
@@ -417,8 +664,647 @@ mod test {
         public_input_puzzle.reverse();
 
         // Prove as normal
-        let public_inputs = vec![public_input_maingate, public_input_puzzle];
+        let public_input_solver = vec![Fp::zero()];
+        let public_inputs = vec![
+            public_input_maingate,
+            public_input_puzzle,
+            public_input_solver,
+        ];
         let prover = MockProver::run(k, &circuit, public_inputs).unwrap();
         assert!(prover.verify().is_err());
     }
+
+    // The following solutions are built by cyclically shifting a row that sums to 45 but
+    // repeats some digits. Every row, column and square sums to 45 and no two neighbouring
+    // cells are equal, so they slipped past the old adjacent-pairs uniqueness check.
+
+    #[test]
+    fn test_adversarial_solution_with_blank_puzzle() {
+        let puzzle = Array2::zeros((9, 9));
+        let solution = array![
+            [4, 9, 4, 2, 6, 7, 3, 1, 9],
+            [2, 6, 7, 3, 1, 9, 4, 9, 4],
+            [3, 1, 9, 4, 9, 4, 2, 6, 7],
+            [9, 4, 2, 6, 7, 3, 1, 9, 4],
+            [6, 7, 3, 1, 9, 4, 9, 4, 2],
+            [1, 9, 4, 9, 4, 2, 6, 7, 3],
+            [4, 2, 6, 7, 3, 1, 9, 4, 9],
+            [7, 3, 1, 9, 4, 9, 4, 2, 6],
+            [9, 4, 9, 4, 2, 6, 7, 3, 1],
+        ];
+        let result = prove_and_verify_circuit(puzzle, solution);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_adversarial_solution_matching_puzzle_clues() {
+        let puzzle = array![
+            [9, 0, 0, 0, 7, 0, 0, 0, 4],
+            [0, 0, 0, 8, 0, 0, 0, 3, 0],
+            [0, 0, 4, 0, 0, 0, 1, 0, 0],
+            [0, 9, 0, 0, 0, 8, 0, 0, 0],
+            [7, 0, 0, 0, 4, 0, 0, 0, 1],
+            [0, 0, 0, 3, 0, 0, 0, 2, 0],
+            [0, 0, 7, 0, 0, 0, 4, 0, 0],
+            [0, 8, 0, 0, 0, 3, 0, 0, 0],
+            [4, 0, 0, 0, 1, 0, 0, 0, 2],
+        ];
+        let solution = array![
+            [9, 3, 9, 1, 7, 2, 8, 2, 4],
+            [1, 7, 2, 8, 2, 4, 9, 3, 9],
+            [8, 2, 4, 9, 3, 9, 1, 7, 2],
+            [3, 9, 1, 7, 2, 8, 2, 4, 9],
+            [7, 2, 8, 2, 4, 9, 3, 9, 1],
+            [2, 4, 9, 3, 9, 1, 7, 2, 8],
+            [9, 1, 7, 2, 8, 2, 4, 9, 3],
+            [2, 8, 2, 4, 9, 3, 9, 1, 7],
+            [4, 9, 3, 9, 1, 7, 2, 8, 2],
+        ];
+        let result = prove_and_verify_circuit(puzzle, solution);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_solution_cell_zero() {
+        let puzzle = fixtures::puzzle_cells();
+        let mut solution = fixtures::solution_cells();
+        solution[[0, 0]] = 0; // top-left cell changed from 4 to 0, which is unset in the puzzle
+        let failures = prove_and_verify_circuit(puzzle, solution).unwrap_err();
+        assert!(failures
+            .iter()
+            .any(|failure| format!("{:?}", failure).contains("solution range check")));
+    }
+
+    #[test]
+    fn test_solution_all_zeros() {
+        let puzzle = Array2::zeros((9, 9));
+        let solution = Array2::zeros((9, 9));
+        let failures = prove_and_verify_circuit(puzzle, solution).unwrap_err();
+        assert!(failures
+            .iter()
+            .any(|failure| format!("{:?}", failure).contains("solution range check")));
+        assert!(!failures
+            .iter()
+            .any(|failure| format!("{:?}", failure).contains("puzzle range check")));
+    }
+
+    #[test]
+    fn test_lookup_range_check() {
+        use range::LookupRangeCheck;
+
+        let puzzle = fixtures::puzzle_cells();
+        let solution = fixtures::solution_cells();
+        let result = prove_and_verify_circuit_with_range_check::<LookupRangeCheck>(
+            puzzle.clone(),
+            solution.clone(),
+        );
+        assert_eq!(result, Ok(()));
+
+        // a solution cell above the allowed range
+        let mut bad_solution = solution.clone();
+        bad_solution[[0, 0]] = 10;
+        let result = prove_and_verify_circuit_with_range_check::<LookupRangeCheck>(
+            puzzle.clone(),
+            bad_solution,
+        );
+        assert!(matches!(
+            result.unwrap_err().as_slice(),
+            failures if failures.iter().any(|f| matches!(f, VerifyFailure::Lookup { .. }))
+        ));
+
+        // a solution cell of zero, which the puzzle table allows but the solution table doesn't
+        let mut bad_solution = solution;
+        bad_solution[[0, 0]] = 0;
+        let result =
+            prove_and_verify_circuit_with_range_check::<LookupRangeCheck>(puzzle, bad_solution);
+        assert!(matches!(
+            result.unwrap_err().as_slice(),
+            failures if failures.iter().any(|f| matches!(f, VerifyFailure::Lookup { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_lookup_range_check_lowers_degree() {
+        use halo2wrong::halo2::halo2curves::bn256::Fr as Fp;
+        use range::LookupRangeCheck;
+
+        let mut polynomial_cs = ConstraintSystem::<Fp>::default();
+        SudokuCircuit::<Fp, 9, PolynomialRangeCheck>::configure(&mut polynomial_cs);
+        let mut lookup_cs = ConstraintSystem::<Fp>::default();
+        SudokuCircuit::<Fp, 9, LookupRangeCheck>::configure(&mut lookup_cs);
+
+        assert!(lookup_cs.degree() < polynomial_cs.degree());
+    }
+
+    // Relabelling the digits 1 and 9 of a valid solution as 0 and 10 keeps every row, column
+    // and square pairwise distinct and summing to 45, so the only thing rejecting this solution
+    // is the range check. If the range check ever looked at a different cell than the one fed
+    // into the later constraints, we would see either no failures or failures on other rows.
+    fn relabelled_solution_failures<R: RangeCheckKind>() -> Vec<VerifyFailure> {
+        let puzzle = Array2::zeros((9, 9));
+        let solution = fixtures::solution_cells().mapv(|value| match value {
+            1 => 0,
+            9 => 10,
+            value => value,
+        });
+        prove_and_verify_circuit_with_range_check::<R>(puzzle, solution).unwrap_err()
+    }
+
+    #[test]
+    fn test_range_checked_cells_are_the_constrained_cells() {
+        use range::LookupRangeCheck;
+
+        let failures = relabelled_solution_failures::<PolynomialRangeCheck>();
+        assert_eq!(failures.len(), 18);
+        assert!(failures
+            .iter()
+            .all(|failure| format!("{:?}", failure).contains("solution range check")));
+
+        let failures = relabelled_solution_failures::<LookupRangeCheck>();
+        assert_eq!(failures.len(), 18);
+        assert!(failures
+            .iter()
+            .all(|failure| matches!(failure, VerifyFailure::Lookup { .. })));
+    }
+
+    #[test]
+    fn test_proof_bound_to_solver() {
+        use halo2wrong::halo2::{dev::MockProver, halo2curves::bn256::Fr as Fp};
+
+        let puzzle = fixtures::puzzle_cells();
+        let solution = fixtures::solution_cells();
+        let solver = Fp::from(0xa11ce_u64);
+        let other_solver = Fp::from(0xb0b_u64);
+        let circuit: SudokuCircuit<Fp> = unchecked_circuit(puzzle, solution).with_solver_id(solver);
+
+        let prover =
+            MockProver::run(K, &circuit, public_inputs(&circuit.puzzle, Some(solver))).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let prover = MockProver::run(
+            K,
+            &circuit,
+            public_inputs(&circuit.puzzle, Some(other_solver)),
+        )
+        .unwrap();
+        assert!(prover.verify().is_err());
+
+        // an unbound proof is checked against a solver id of zero
+        let prover = MockProver::run(K, &circuit, public_inputs(&circuit.puzzle, None)).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    // run_hashed_puzzle runs the circuit exposing the puzzle as a Poseidon hash against the
+    // public inputs committing to `public_puzzle`
+    fn run_hashed_puzzle(
+        puzzle: Array2<u8>,
+        solution: Array2<u8>,
+        public_puzzle: &Array2<u8>,
+    ) -> Result<(), Vec<VerifyFailure>> {
+        use commitment::PoseidonPuzzleHash;
+        use halo2wrong::halo2::{dev::MockProver, halo2curves::bn256::Fr as Fp};
+
+        let circuit: SudokuCircuit<Fp, 9, PolynomialRangeCheck, PoseidonPuzzleHash> =
+            unchecked_circuit(puzzle, solution);
+        let public_inputs = public_inputs_with_commitment(
+            &Puzzle::<9>::new_unchecked(public_puzzle.clone()),
+            None,
+            PuzzleCommitmentMode::PoseidonHash,
+        );
+
+        let prover = MockProver::run(K_HASHED_PUZZLE, &circuit, public_inputs).unwrap();
+        prover.verify()
+    }
+
+    #[test]
+    fn test_hashed_puzzle() {
+        let puzzle = fixtures::puzzle_cells();
+        let solution = fixtures::solution_cells();
+        let result = run_hashed_puzzle(puzzle.clone(), solution, &puzzle);
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn test_hashed_puzzle_mismatch() {
+        let puzzle = fixtures::puzzle_cells();
+        let solution = fixtures::solution_cells();
+
+        // the solution also solves this puzzle with one fewer clue, but its digest differs
+        let mut other_puzzle = puzzle.clone();
+        other_puzzle[[0, 3]] = 0;
+        let result = run_hashed_puzzle(puzzle.clone(), solution.clone(), &other_puzzle);
+        assert!(result.is_err());
+
+        // and the circuit can't be made to hash a puzzle other than the one it checks against
+        let result = run_hashed_puzzle(other_puzzle, solution, &puzzle);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_hash_puzzle_is_order_sensitive() {
+        use halo2wrong::halo2::halo2curves::bn256::Fr as Fp;
+
+        // transposing moves cells around without changing which values appear
+        let cells = Array2::from_shape_fn((9, 9), |(row, col)| (row * 9 + col) as u8 % 10);
+        let puzzle = Puzzle::<9>::new(cells.clone()).unwrap();
+        let transposed = Puzzle::<9>::new(cells.t().to_owned()).unwrap();
+        assert_ne!(
+            commitment::hash_puzzle::<Fp, 9>(&puzzle),
+            commitment::hash_puzzle::<Fp, 9>(&transposed)
+        );
+    }
+
+    // pattern_solution returns a valid NxN solution, built by shifting each row of the
+    // first one so that every row, column and box holds each digit once
+    fn pattern_solution<const N: usize>() -> Array2<u8> {
+        use halo2wrong::halo2::halo2curves::bn256::Fr as Fp;
+
+        let box_size = SudokuCircuit::<Fp, N>::BOX_SIZE;
+        Array2::from_shape_fn((N, N), |(row, col)| {
+            ((box_size * (row % box_size) + row / box_size + col) % N + 1) as u8
+        })
+    }
+
+    // prove_and_verify_board runs an NxN circuit under the mock prover, with k chosen to fit
+    fn prove_and_verify_board<const N: usize>(
+        puzzle: Array2<u8>,
+        solution: Array2<u8>,
+    ) -> Result<(), Vec<VerifyFailure>> {
+        use halo2wrong::halo2::{dev::MockProver, halo2curves::bn256::Fr as Fp};
+
+        let circuit: SudokuCircuit<Fp, N> = unchecked_circuit(puzzle, solution);
+        let public_inputs = public_inputs(&circuit.puzzle, circuit.solver_id);
+
+        let prover = MockProver::run(circuit.min_k().unwrap(), &circuit, public_inputs).unwrap();
+        prover.verify()
+    }
+
+    #[test]
+    fn test_4x4_board() {
+        let puzzle = array![[1, 0, 0, 4], [0, 4, 1, 0], [2, 0, 0, 3], [0, 3, 2, 0]];
+        let solution = array![[1, 2, 3, 4], [3, 4, 1, 2], [2, 1, 4, 3], [4, 3, 2, 1]];
+        let result = prove_and_verify_board::<4>(puzzle.clone(), solution.clone());
+        assert_eq!(result, Ok(()));
+
+        // swapping two cells of a row keeps the row valid but breaks both columns
+        let mut incorrect_solution = solution.clone();
+        incorrect_solution.swap([0, 0], [0, 1]);
+        let result = prove_and_verify_board::<4>(Array2::zeros((4, 4)), incorrect_solution);
+        assert!(result.is_err());
+
+        // 5 is a valid digit on a 9x9 board, but not on a 4x4 one
+        let mut out_of_range_solution = solution;
+        out_of_range_solution[[0, 0]] = 5;
+        let result = prove_and_verify_board::<4>(puzzle, out_of_range_solution);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_16x16_board() {
+        let solution = pattern_solution::<16>();
+        let puzzle = Array2::from_shape_fn((16, 16), |(row, col)| {
+            if (row + col) % 3 == 0 {
+                solution[[row, col]]
+            } else {
+                0
+            }
+        });
+        let result = prove_and_verify_board::<16>(puzzle.clone(), solution.clone());
+        assert_eq!(result, Ok(()));
+
+        let mut incorrect_solution = solution;
+        incorrect_solution.swap([0, 1], [0, 2]);
+        let result = prove_and_verify_board::<16>(Array2::zeros((16, 16)), incorrect_solution);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_min_k() {
+        use halo2wrong::halo2::halo2curves::bn256::Fr as Fp;
+
+        let solution = pattern_solution::<9>();
+        let circuit: SudokuCircuit<Fp> = unchecked_circuit(Array2::zeros((9, 9)), solution);
+        assert!(circuit.min_k().unwrap() <= K);
+
+        let small: SudokuCircuit<Fp, 4> =
+            unchecked_circuit(Array2::zeros((4, 4)), pattern_solution::<4>());
+        let large: SudokuCircuit<Fp, 16> =
+            unchecked_circuit(Array2::zeros((16, 16)), pattern_solution::<16>());
+        assert!(small.min_k().unwrap() < circuit.min_k().unwrap());
+        assert!(large.min_k().unwrap() > circuit.min_k().unwrap());
+    }
+
+    #[test]
+    fn test_parsed_boards() {
+        use halo2wrong::halo2::{dev::MockProver, halo2curves::bn256::Fr as Fp};
+
+        let puzzle = fixtures::puzzle();
+        let solution = fixtures::solution();
+        let circuit: SudokuCircuit<Fp> = SudokuCircuit::new(puzzle, solution).unwrap();
+
+        let public_inputs = public_inputs(circuit.puzzle(), circuit.solver_id());
+        let prover = MockProver::run(K, &circuit, public_inputs).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn test_from_puzzle_solving() {
+        use halo2wrong::halo2::{dev::MockProver, halo2curves::bn256::Fr as Fp};
+
+        let puzzle: Puzzle =
+            "800000000003600000070090200050007000000045700000100030001000068008500010090000400"
+                .parse()
+                .unwrap();
+        let circuit: SudokuCircuit<Fp> = SudokuCircuit::from_puzzle_solving(puzzle).unwrap();
+
+        let public_inputs = public_inputs(circuit.puzzle(), circuit.solver_id());
+        let prover = MockProver::run(K, &circuit, public_inputs).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let result: Result<SudokuCircuit<Fp>, _> =
+            SudokuCircuit::from_puzzle_solving(Puzzle::blank());
+        assert_eq!(result.unwrap_err(), SolveError::MultipleSolutions);
+    }
+
+    #[test]
+    fn test_synthesis_errors_are_returned() {
+        use halo2wrong::halo2::{dev::MockProver, halo2curves::bn256::Fr as Fp};
+
+        // 2^4 rows can't hold the board, so synthesis must fail while loading it. The public
+        // inputs are left out, as they wouldn't fit either
+        let circuit: SudokuCircuit<Fp> =
+            unchecked_circuit(Array2::zeros((9, 9)), pattern_solution::<9>());
+        let result = MockProver::run(4, &circuit, vec![vec![], vec![], vec![]]);
+        assert!(matches!(result, Err(Error::NotEnoughRowsAvailable { .. })));
+
+        let result: Result<_, SudokuError> = result.map_err(SudokuError::from);
+        assert!(matches!(result, Err(SudokuError::Plonk(_))));
+    }
+
+    #[test]
+    fn test_layout() {
+        use halo2::dev::{circuit_dot_graph, CircuitLayout};
+        use halo2wrong::halo2::halo2curves::bn256::Fr as Fp;
+        use plotters::prelude::*;
+
+        let circuit: SudokuCircuit<Fp> =
+            unchecked_circuit(Array2::zeros((9, 9)), pattern_solution::<9>());
+
+        // every phase of synthesis shows up as labelled regions, named after the cells and
+        // groups they check
+        let mut svg = String::new();
+        {
+            let root = SVGBackend::with_string(&mut svg, (1024, 3072)).into_drawing_area();
+            CircuitLayout::default()
+                .show_labels(true)
+                .render(K, &circuit, &root)
+                .unwrap();
+        }
+        for region in [
+            "load boards",
+            "cell (0,0)",
+            "row 0",
+            "column 8",
+            "box (2,2)",
+        ] {
+            assert!(
+                svg.contains(region),
+                "{} is missing from the layout",
+                region
+            );
+        }
+
+        assert!(circuit_dot_graph(&circuit).contains("digraph"));
+    }
+
+    fn prove_and_verify_circuit_with_group_check<G: GroupCheckStrategy>(
+        puzzle: Array2<u8>,
+        solution: Array2<u8>,
+    ) -> Result<(), Vec<VerifyFailure>> {
+        use halo2wrong::halo2::{dev::MockProver, halo2curves::bn256::Fr as Fp};
+
+        let circuit: SudokuCircuit<Fp, 9, PolynomialRangeCheck, PuzzleCells, G> =
+            unchecked_circuit(puzzle, solution);
+        let public_inputs = public_inputs(&circuit.puzzle, circuit.solver_id);
+
+        let prover = MockProver::run(circuit.min_k().unwrap(), &circuit, public_inputs).unwrap();
+        prover.verify()
+    }
+
+    // check_group_strategy runs the same valid, invalid and adversarial solutions through
+    // each group check, so that their soundness can be compared side by side
+    fn check_group_strategy<G: GroupCheckStrategy>() {
+        let puzzle = fixtures::puzzle_cells();
+        let solution = fixtures::solution_cells();
+        let result = prove_and_verify_circuit_with_group_check::<G>(puzzle, solution.clone());
+        assert_eq!(result, Ok(()));
+
+        // swapping two cells of a row keeps the row valid but breaks both columns
+        let mut incorrect_solution = solution;
+        incorrect_solution.swap([0, 0], [0, 1]);
+        let result = prove_and_verify_circuit_with_group_check::<G>(
+            Array2::zeros((9, 9)),
+            incorrect_solution,
+        );
+        assert!(result.is_err());
+
+        // see `test_adversarial_solution_with_blank_puzzle`
+        let adversarial_solution = array![
+            [4, 9, 4, 2, 6, 7, 3, 1, 9],
+            [2, 6, 7, 3, 1, 9, 4, 9, 4],
+            [3, 1, 9, 4, 9, 4, 2, 6, 7],
+            [9, 4, 2, 6, 7, 3, 1, 9, 4],
+            [6, 7, 3, 1, 9, 4, 9, 4, 2],
+            [1, 9, 4, 9, 4, 2, 6, 7, 3],
+            [4, 2, 6, 7, 3, 1, 9, 4, 9],
+            [7, 3, 1, 9, 4, 9, 4, 2, 6],
+            [9, 4, 9, 4, 2, 6, 7, 3, 1],
+        ];
+        let result = prove_and_verify_circuit_with_group_check::<G>(
+            Array2::zeros((9, 9)),
+            adversarial_solution,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_pairwise_distinct_group_check() {
+        check_group_strategy::<PairwiseDistinct>();
+    }
+
+    #[test]
+    fn test_fingerprint_group_check() {
+        check_group_strategy::<group::Fingerprint>();
+    }
+
+    #[test]
+    fn test_shuffle_group_check() {
+        check_group_strategy::<group::Shuffle>();
+    }
+
+    #[test]
+    fn test_index_hint_group_check() {
+        check_group_strategy::<group::IndexHint>();
+    }
+
+    #[test]
+    fn test_multiset_product_group_check() {
+        check_group_strategy::<group::MultisetProduct>();
+    }
+
+    // group_check_cost returns the smallest k the standard solution fits in, along with
+    // the max degree of the circuit's constraints and its number of advice columns, when
+    // groups are checked with `G`
+    fn group_check_cost<G: GroupCheckStrategy>() -> (u32, usize, usize) {
+        use halo2wrong::halo2::halo2curves::bn256::Fr as Fp;
+
+        let circuit: SudokuCircuit<Fp, 9, PolynomialRangeCheck, PuzzleCells, G> =
+            unchecked_circuit(Array2::zeros((9, 9)), pattern_solution::<9>());
+        let mut cs = ConstraintSystem::<Fp>::default();
+        let config =
+            SudokuCircuit::<Fp, 9, PolynomialRangeCheck, PuzzleCells, G>::configure(&mut cs);
+        assert_eq!(config.group_check_mode(), G::MODE);
+
+        (
+            circuit.min_k().unwrap(),
+            cs.degree(),
+            cs.num_advice_columns(),
+        )
+    }
+
+    // Run with `cargo test test_group_check_costs -- --nocapture` to see the costs of every
+    // group check side by side
+    #[test]
+    fn test_group_check_costs() {
+        let costs = [
+            ("pairwise distinct", group_check_cost::<PairwiseDistinct>()),
+            ("fingerprint", group_check_cost::<group::Fingerprint>()),
+            ("shuffle", group_check_cost::<group::Shuffle>()),
+            ("index hint", group_check_cost::<group::IndexHint>()),
+            (
+                "multiset product",
+                group_check_cost::<group::MultisetProduct>(),
+            ),
+        ];
+        for (name, (k, degree, advice_columns)) in costs {
+            println!(
+                "{:>17}: k = {:>2}, degree = {:>2}, advice columns = {}",
+                name, k, degree, advice_columns
+            );
+        }
+
+        // the product gate replaces the 36 pairwise comparisons per group, which are what
+        // push the default circuit past 2^11 rows
+        let (pairwise_k, _, pairwise_columns) = costs[0].1;
+        let (product_k, _, product_columns) = costs[4].1;
+        assert!(product_k < pairwise_k);
+        assert!(product_columns > pairwise_columns);
+    }
+
+    type IndexHintCircuit = SudokuCircuit<
+        halo2wrong::halo2::halo2curves::bn256::Fr,
+        9,
+        PolynomialRangeCheck,
+        PuzzleCells,
+        group::IndexHint,
+    >;
+
+    // index_hint_circuit builds a circuit checking the standard solution with the index hint
+    // group check, with its hints tampered with by `tamper`
+    fn index_hint_circuit(tamper: impl FnOnce(&mut Vec<Vec<usize>>)) -> IndexHintCircuit {
+        let puzzle = fixtures::puzzle_cells();
+        let solution = fixtures::solution_cells();
+        let circuit = unchecked_circuit(puzzle, solution);
+        let mut digit_positions = circuit.digit_positions();
+        tamper(&mut digit_positions);
+        circuit.with_digit_positions(digit_positions)
+    }
+
+    // run_with_digit_positions runs the index hint circuit built by `index_hint_circuit`
+    // through the mock prover
+    fn run_with_digit_positions(
+        tamper: impl FnOnce(&mut Vec<Vec<usize>>),
+    ) -> Result<(), Vec<VerifyFailure>> {
+        use halo2wrong::halo2::dev::MockProver;
+
+        let circuit = index_hint_circuit(tamper);
+        let public_inputs = public_inputs(&circuit.puzzle, circuit.solver_id);
+        let prover = MockProver::run(circuit.min_k().unwrap(), &circuit, public_inputs).unwrap();
+        prover.verify()
+    }
+
+    #[test]
+    fn test_generated_digit_positions() {
+        use halo2wrong::halo2::halo2curves::bn256::Fr as Fp;
+
+        let solution = pattern_solution::<9>();
+        let circuit: SudokuCircuit<Fp> = unchecked_circuit(Array2::zeros((9, 9)), solution);
+        let groups = SudokuCircuit::<Fp>::groups(circuit.solution.cells());
+        let digit_positions = circuit.digit_positions();
+        assert_eq!(digit_positions.len(), 27);
+
+        for (group, digit_positions) in groups.iter().zip(&digit_positions) {
+            for (digit, &position) in (1..=9).zip(digit_positions) {
+                assert_eq!(group[position], digit);
+            }
+        }
+
+        assert_eq!(run_with_digit_positions(|_| {}), Ok(()));
+    }
+
+    #[test]
+    fn test_malformed_digit_positions() {
+        // pointing at a cell holding another digit
+        let result = run_with_digit_positions(|digit_positions| {
+            digit_positions[0].swap(0, 1);
+        });
+        assert!(result.is_err());
+
+        // pointing past the end of the group
+        let result = run_with_digit_positions(|digit_positions| {
+            digit_positions[9][4] = 9;
+        });
+        assert!(result.is_err());
+
+        // pointing far past the end of the group
+        let result = run_with_digit_positions(|digit_positions| {
+            digit_positions[18][8] = usize::MAX;
+        });
+        assert!(result.is_err());
+
+        // pointing two digits at the same cell
+        let result = run_with_digit_positions(|digit_positions| {
+            digit_positions[26][2] = digit_positions[26][3];
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_missing_digit_positions() {
+        use halo2wrong::halo2::dev::MockProver;
+
+        let run = |tamper: fn(&mut Vec<Vec<usize>>)| {
+            let circuit = index_hint_circuit(tamper);
+            let public_inputs = public_inputs(&circuit.puzzle, circuit.solver_id);
+            MockProver::run(K, &circuit, public_inputs).map(|_| ())
+        };
+
+        // a group without a hint
+        let result = run(|digit_positions| {
+            digit_positions.pop();
+        });
+        assert!(matches!(result, Err(Error::Synthesis)));
+
+        // a digit without a position
+        let result = run(|digit_positions| {
+            digit_positions[4].pop();
+        });
+        assert!(matches!(result, Err(Error::Synthesis)));
+
+        // a position too many
+        let result = run(|digit_positions| {
+            digit_positions[13].push(0);
+        });
+        assert!(matches!(result, Err(Error::Synthesis)));
+    }
 }
@@ -0,0 +1,324 @@
+//! Real (non-mock) key generation, proving and verification for [`SudokuCircuit`].
+//!
+//! Proofs can be made with either of two polynomial commitment schemes, selected by the type
+//! of the parameters passed in:
+//! - KZG over bn256 (`ParamsKZG<Bn256>`), which needs a trusted setup but yields small proofs
+//!   that are cheap to verify on-chain
+//! - IPA over the Pasta curves (`ParamsIPA<EqAffine>`), which has a transparent setup
+//!
+//! Both use a Blake2b transcript.
+
+use crate::{public_inputs, Puzzle, SudokuCircuit};
+use halo2wrong::halo2::{
+    arithmetic::CurveAffine,
+    halo2curves::{
+        bn256::{Bn256, G1Affine},
+        pasta::EqAffine,
+    },
+    plonk::{
+        create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, Error, ProvingKey, VerifyingKey,
+    },
+    poly::{
+        commitment::ParamsProver,
+        ipa::{
+            commitment::{IPACommitmentScheme, ParamsIPA},
+            multiopen::{ProverIPA, VerifierIPA},
+            strategy::SingleStrategy as IPASingleStrategy,
+        },
+        kzg::{
+            commitment::{KZGCommitmentScheme, ParamsKZG},
+            multiopen::{ProverSHPLONK, VerifierSHPLONK},
+            strategy::SingleStrategy as KZGSingleStrategy,
+        },
+    },
+    transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+    },
+};
+use rand_core::RngCore;
+use std::fmt;
+
+/// The scalar field of the circuits proven with parameters `P`
+pub type Scalar<P> = <<P as CommitmentParams>::Curve as CurveAffine>::ScalarExt;
+
+/// The public parameters of a polynomial commitment scheme that sudoku proofs can be made with.
+/// Implemented for `ParamsKZG<Bn256>` and `ParamsIPA<EqAffine>`.
+pub trait CommitmentParams: Sized {
+    /// The curve commitments are made over
+    type Curve: CurveAffine;
+
+    /// Generates fresh parameters for circuits of up to 2^k rows
+    fn setup_params(k: u32, rng: impl RngCore) -> Self;
+
+    fn keygen_vk<C: Circuit<Scalar<Self>>>(
+        &self,
+        circuit: &C,
+    ) -> Result<VerifyingKey<Self::Curve>, Error>;
+
+    fn keygen_pk<C: Circuit<Scalar<Self>>>(
+        &self,
+        vk: VerifyingKey<Self::Curve>,
+        circuit: &C,
+    ) -> Result<ProvingKey<Self::Curve>, Error>;
+
+    /// Proves `circuit` with the given public inputs, one slice per instance column,
+    /// returning the serialized proof
+    fn create_proof<C: Circuit<Scalar<Self>>>(
+        &self,
+        pk: &ProvingKey<Self::Curve>,
+        circuit: C,
+        instances: &[&[Scalar<Self>]],
+        rng: impl RngCore,
+    ) -> Result<Vec<u8>, Error>;
+
+    fn verify_proof(
+        &self,
+        vk: &VerifyingKey<Self::Curve>,
+        instances: &[&[Scalar<Self>]],
+        proof: &[u8],
+    ) -> Result<(), Error>;
+}
+
+impl CommitmentParams for ParamsKZG<Bn256> {
+    type Curve = G1Affine;
+
+    fn setup_params(k: u32, rng: impl RngCore) -> Self {
+        ParamsKZG::setup(k, rng)
+    }
+
+    fn keygen_vk<C: Circuit<Scalar<Self>>>(
+        &self,
+        circuit: &C,
+    ) -> Result<VerifyingKey<G1Affine>, Error> {
+        keygen_vk(self, circuit)
+    }
+
+    fn keygen_pk<C: Circuit<Scalar<Self>>>(
+        &self,
+        vk: VerifyingKey<G1Affine>,
+        circuit: &C,
+    ) -> Result<ProvingKey<G1Affine>, Error> {
+        keygen_pk(self, vk, circuit)
+    }
+
+    fn create_proof<C: Circuit<Scalar<Self>>>(
+        &self,
+        pk: &ProvingKey<G1Affine>,
+        circuit: C,
+        instances: &[&[Scalar<Self>]],
+        rng: impl RngCore,
+    ) -> Result<Vec<u8>, Error> {
+        let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+        create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<'_, Bn256>, _, _, _, _>(
+            self,
+            pk,
+            &[circuit],
+            &[instances],
+            rng,
+            &mut transcript,
+        )?;
+
+        Ok(transcript.finalize())
+    }
+
+    fn verify_proof(
+        &self,
+        vk: &VerifyingKey<G1Affine>,
+        instances: &[&[Scalar<Self>]],
+        proof: &[u8],
+    ) -> Result<(), Error> {
+        let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(proof);
+        verify_proof::<KZGCommitmentScheme<Bn256>, VerifierSHPLONK<'_, Bn256>, _, _, _>(
+            self.verifier_params(),
+            vk,
+            KZGSingleStrategy::new(self),
+            &[instances],
+            &mut transcript,
+        )
+    }
+}
+
+impl CommitmentParams for ParamsIPA<EqAffine> {
+    type Curve = EqAffine;
+
+    // IPA has a transparent setup, so no randomness is needed
+    fn setup_params(k: u32, _rng: impl RngCore) -> Self {
+        ParamsIPA::new(k)
+    }
+
+    fn keygen_vk<C: Circuit<Scalar<Self>>>(
+        &self,
+        circuit: &C,
+    ) -> Result<VerifyingKey<EqAffine>, Error> {
+        keygen_vk(self, circuit)
+    }
+
+    fn keygen_pk<C: Circuit<Scalar<Self>>>(
+        &self,
+        vk: VerifyingKey<EqAffine>,
+        circuit: &C,
+    ) -> Result<ProvingKey<EqAffine>, Error> {
+        keygen_pk(self, vk, circuit)
+    }
+
+    fn create_proof<C: Circuit<Scalar<Self>>>(
+        &self,
+        pk: &ProvingKey<EqAffine>,
+        circuit: C,
+        instances: &[&[Scalar<Self>]],
+        rng: impl RngCore,
+    ) -> Result<Vec<u8>, Error> {
+        let mut transcript = Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(vec![]);
+        create_proof::<IPACommitmentScheme<EqAffine>, ProverIPA<'_, EqAffine>, _, _, _, _>(
+            self,
+            pk,
+            &[circuit],
+            &[instances],
+            rng,
+            &mut transcript,
+        )?;
+
+        Ok(transcript.finalize())
+    }
+
+    fn verify_proof(
+        &self,
+        vk: &VerifyingKey<EqAffine>,
+        instances: &[&[Scalar<Self>]],
+        proof: &[u8],
+    ) -> Result<(), Error> {
+        let mut transcript = Blake2bRead::<_, EqAffine, Challenge255<_>>::init(proof);
+        verify_proof::<IPACommitmentScheme<EqAffine>, VerifierIPA<'_, EqAffine>, _, _, _>(
+            self.verifier_params(),
+            vk,
+            IPASingleStrategy::new(self),
+            &[instances],
+            &mut transcript,
+        )
+    }
+}
+
+/// The reasons a proof can fail to verify.
+#[derive(Debug)]
+pub enum VerifyError {
+    /// The proof could not be parsed, or does not prove knowledge of a solution to the puzzle
+    InvalidProof(Error),
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::InvalidProof(err) => write!(f, "invalid proof: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// Generates parameters just large enough for [`SudokuCircuit`], with `k` taken from its
+/// [`stats`](SudokuCircuit::stats).
+pub fn setup<P: CommitmentParams>(rng: impl RngCore) -> Result<P, Error> {
+    let stats = SudokuCircuit::<Scalar<P>>::default().stats()?;
+    Ok(P::setup_params(stats.min_k, rng))
+}
+
+/// Generates the proving and verifying keys for [`SudokuCircuit`]. The keys only depend on the
+/// shape of the circuit, so they can be generated once and reused for every puzzle.
+pub fn keygen<P: CommitmentParams>(
+    params: &P,
+) -> Result<(ProvingKey<P::Curve>, VerifyingKey<P::Curve>), Error> {
+    let circuit = SudokuCircuit::<Scalar<P>>::default().without_witnesses();
+    let vk = params.keygen_vk(&circuit)?;
+    let pk = params.keygen_pk(vk.clone(), &circuit)?;
+
+    Ok((pk, vk))
+}
+
+/// Proves knowledge of the solution held by `circuit` to its public puzzle, returning the
+/// serialized proof. If the circuit has a solver id, the proof only verifies for that solver.
+pub fn prove<P: CommitmentParams>(
+    params: &P,
+    pk: &ProvingKey<P::Curve>,
+    circuit: SudokuCircuit<Scalar<P>>,
+    rng: impl RngCore,
+) -> Result<Vec<u8>, Error> {
+    let instances: Vec<Vec<Scalar<P>>> = public_inputs(circuit.puzzle(), circuit.solver_id());
+    let instances: Vec<&[Scalar<P>]> = instances.iter().map(Vec::as_slice).collect();
+
+    params.create_proof(pk, circuit, &instances, rng)
+}
+
+/// Checks that `proof` proves knowledge of a solution to the public `puzzle`, and that it was
+/// made for `solver_id` (or for no solver in particular, if `None`).
+pub fn verify<P: CommitmentParams>(
+    params: &P,
+    vk: &VerifyingKey<P::Curve>,
+    puzzle: &Puzzle,
+    solver_id: Option<Scalar<P>>,
+    proof: &[u8],
+) -> Result<(), VerifyError> {
+    let instances: Vec<Vec<Scalar<P>>> = public_inputs(puzzle, solver_id);
+    let instances: Vec<&[Scalar<P>]> = instances.iter().map(Vec::as_slice).collect();
+
+    params
+        .verify_proof(vk, &instances, proof)
+        .map_err(VerifyError::InvalidProof)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures;
+    use rand::rngs::OsRng;
+
+    fn round_trip<P: CommitmentParams>() {
+        // the parameters are sized by the circuit's stats
+        let params: P = setup(OsRng).unwrap();
+        let (pk, vk) = keygen(&params).unwrap();
+        let (puzzle, solution) = (fixtures::puzzle(), fixtures::solution());
+
+        let solver = Some(Scalar::<P>::from(0xa11ce_u64));
+        let other_solver = Some(Scalar::<P>::from(0xb0b_u64));
+
+        let circuit = SudokuCircuit::new(puzzle.clone(), solution)
+            .unwrap()
+            .with_solver_id(solver.unwrap());
+        let proof = prove(&params, &pk, circuit, OsRng).unwrap();
+        assert!(verify(&params, &vk, &puzzle, solver, &proof).is_ok());
+
+        // the same proof must not verify against a different puzzle
+        let mut other_puzzle = puzzle.cells().clone();
+        other_puzzle[[0, 0]] = 4;
+        let other_puzzle = Puzzle::new(other_puzzle).unwrap();
+        assert!(matches!(
+            verify(&params, &vk, &other_puzzle, solver, &proof),
+            Err(VerifyError::InvalidProof(_))
+        ));
+
+        // nor for a different solver, or for no solver in particular
+        assert!(matches!(
+            verify(&params, &vk, &puzzle, other_solver, &proof),
+            Err(VerifyError::InvalidProof(_))
+        ));
+        assert!(matches!(
+            verify(&params, &vk, &puzzle, None, &proof),
+            Err(VerifyError::InvalidProof(_))
+        ));
+
+        // nor must a tampered proof verify against the original puzzle
+        let mut tampered_proof = proof;
+        let last = tampered_proof.len() - 1;
+        tampered_proof[last] ^= 1;
+        assert!(verify(&params, &vk, &puzzle, solver, &tampered_proof).is_err());
+    }
+
+    #[test]
+    fn test_prove_and_verify_kzg() {
+        round_trip::<ParamsKZG<Bn256>>();
+    }
+
+    #[test]
+    fn test_prove_and_verify_ipa() {
+        round_trip::<ParamsIPA<EqAffine>>();
+    }
+}
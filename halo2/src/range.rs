@@ -0,0 +1,233 @@
+//! Range checks for the cells of a sudoku board.
+//!
+//! On an NxN board, puzzle cells must lie in [0, N] (zero marks an unset cell) and solution
+//! cells must lie in [1, N]. Two interchangeable implementations are provided, selected via [`RangeCheckMode`]:
+//! a small polynomial gate, and a lookup into a fixed table of allowed values.
+
+use crate::utilities::range_check;
+use halo2wrong::halo2::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, Value},
+    plonk::{
+        Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector, TableColumn,
+    },
+    poly::Rotation,
+};
+use std::fmt::Debug;
+
+/// Inclusive lower bound on the value of a solution cell (puzzle cells may also be zero)
+pub const MIN_SOLUTION_CELL_VALUE: u64 = 1;
+
+/// The two ways of constraining a cell to a small range of values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RangeCheckMode {
+    /// A custom gate enforcing `x * (x - 1) * ... * (x - N) = 0`. Needs no extra columns,
+    /// but its degree grows with the size of the range and dominates the circuit's max degree.
+    Polynomial,
+    /// A lookup of each cell into a fixed table holding every allowed value. Costs a table
+    /// column per range, but keeps the lookup argument's degree small and constant.
+    Lookup,
+}
+
+/// Type-level choice of a [`RangeCheckMode`], so that `Circuit::configure` (which has no access
+/// to the circuit instance) knows which range check to build.
+pub trait RangeCheckKind: Clone + Debug + Default {
+    const MODE: RangeCheckMode;
+}
+
+/// Selects [`RangeCheckMode::Polynomial`]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PolynomialRangeCheck;
+
+impl RangeCheckKind for PolynomialRangeCheck {
+    const MODE: RangeCheckMode = RangeCheckMode::Polynomial;
+}
+
+/// Selects [`RangeCheckMode::Lookup`]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LookupRangeCheck;
+
+impl RangeCheckKind for LookupRangeCheck {
+    const MODE: RangeCheckMode = RangeCheckMode::Lookup;
+}
+
+// The fixed tables backing the lookup range check. Each holds every value allowed
+// for the cells it checks, and nothing else.
+#[derive(Clone, Debug)]
+struct RangeCheckTables {
+    puzzle: TableColumn,
+    solution: TableColumn,
+}
+
+/// RangeCheckConfig holds the selectors toggling the puzzle and solution range checks on
+/// for a given row of the checked advice column, along with the lookup tables if any.
+#[derive(Clone, Debug)]
+pub struct RangeCheckConfig {
+    mode: RangeCheckMode,
+
+    // the largest value a cell may hold, i.e. the size N of the board
+    max_cell_value: u64,
+
+    // used to toggle the [0, N] range check on for the rows containing puzzle cells
+    puzzle_selector: Selector,
+
+    // used to toggle the [1, N] range check on for the rows containing solution cells
+    solution_selector: Selector,
+
+    // only present in `RangeCheckMode::Lookup`
+    tables: Option<RangeCheckTables>,
+}
+
+impl RangeCheckConfig {
+    // configure builds range checks for the cells of a board holding values up to
+    // `max_cell_value`
+    pub fn configure<F: FieldExt>(
+        meta: &mut ConstraintSystem<F>,
+        input_column: Column<Advice>,
+        mode: RangeCheckMode,
+        max_cell_value: u64,
+    ) -> Self {
+        match mode {
+            RangeCheckMode::Polynomial => {
+                Self::configure_polynomial(meta, input_column, max_cell_value)
+            }
+            RangeCheckMode::Lookup => Self::configure_lookup(meta, input_column, max_cell_value),
+        }
+    }
+
+    fn configure_polynomial<F: FieldExt>(
+        meta: &mut ConstraintSystem<F>,
+        input_column: Column<Advice>,
+        max_cell_value: u64,
+    ) -> Self {
+        // exclusive upper bound on the value of any cell
+        let range = max_cell_value as usize + 1;
+
+        // enable the puzzle range-check gate: puzzle cells may be zero to mark an unset cell
+        let puzzle_selector = meta.selector();
+        meta.create_gate("puzzle range check", |meta| {
+            let selector = meta.query_selector(puzzle_selector);
+            let input_column: Expression<F> = meta.query_advice(input_column, Rotation::cur());
+
+            let range_check_constraint = Some(range_check(input_column, range));
+
+            Constraints::with_selector(selector, range_check_constraint)
+        });
+
+        // enable the solution range-check gate: every solution cell must hold a digit.
+        // `range_check` only constrains values to [0, range), so we shift the cell down by
+        // one before checking it against [0, range - 1)
+        let solution_selector = meta.selector();
+        meta.create_gate("solution range check", |meta| {
+            let selector = meta.query_selector(solution_selector);
+            let input_column: Expression<F> = meta.query_advice(input_column, Rotation::cur());
+            let shifted = input_column - Expression::Constant(F::from(MIN_SOLUTION_CELL_VALUE));
+
+            let range_check_constraint = Some(range_check(
+                shifted,
+                range - MIN_SOLUTION_CELL_VALUE as usize,
+            ));
+
+            Constraints::with_selector(selector, range_check_constraint)
+        });
+
+        RangeCheckConfig {
+            mode: RangeCheckMode::Polynomial,
+            max_cell_value,
+            puzzle_selector,
+            solution_selector,
+            tables: None,
+        }
+    }
+
+    fn configure_lookup<F: FieldExt>(
+        meta: &mut ConstraintSystem<F>,
+        input_column: Column<Advice>,
+        max_cell_value: u64,
+    ) -> Self {
+        // Selectors used inside lookups must be complex selectors, since a lookup argument
+        // can't be optimised the way simple selectors in custom gates are
+        let puzzle_selector = meta.complex_selector();
+        let solution_selector = meta.complex_selector();
+        let tables = RangeCheckTables {
+            puzzle: meta.lookup_table_column(),
+            solution: meta.lookup_table_column(),
+        };
+
+        // On rows where the selector is off, the looked-up expression collapses to zero,
+        // which the puzzle table holds
+        meta.lookup(|meta| {
+            let selector = meta.query_selector(puzzle_selector);
+            let input_column = meta.query_advice(input_column, Rotation::cur());
+
+            vec![(selector * input_column, tables.puzzle)]
+        });
+
+        // The solution table doesn't hold zero, so on rows where the selector is off we look
+        // up the smallest allowed value instead
+        meta.lookup(|meta| {
+            let selector = meta.query_selector(solution_selector);
+            let input_column = meta.query_advice(input_column, Rotation::cur());
+            let min_value = Expression::Constant(F::from(MIN_SOLUTION_CELL_VALUE));
+            let not_selector = Expression::Constant(F::one()) - selector.clone();
+
+            vec![(
+                selector * input_column + not_selector * min_value,
+                tables.solution,
+            )]
+        });
+
+        RangeCheckConfig {
+            mode: RangeCheckMode::Lookup,
+            max_cell_value,
+            puzzle_selector,
+            solution_selector,
+            tables: Some(tables),
+        }
+    }
+
+    pub fn mode(&self) -> RangeCheckMode {
+        self.mode
+    }
+
+    pub fn puzzle_selector(&self) -> Selector {
+        self.puzzle_selector
+    }
+
+    pub fn solution_selector(&self) -> Selector {
+        self.solution_selector
+    }
+
+    // load_tables fills the lookup tables with the allowed values. It is a no-op in
+    // `RangeCheckMode::Polynomial`, and must be called once per synthesis otherwise
+    pub fn load_tables<F: FieldExt>(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        let tables = match &self.tables {
+            Some(tables) => tables,
+            None => return Ok(()),
+        };
+
+        let puzzle_values = 0..=self.max_cell_value;
+        let solution_values = MIN_SOLUTION_CELL_VALUE..=self.max_cell_value;
+        for (name, column, values) in [
+            ("puzzle range table", tables.puzzle, puzzle_values),
+            ("solution range table", tables.solution, solution_values),
+        ] {
+            layouter.assign_table(
+                || name,
+                |mut table| {
+                    for (offset, value) in values.clone().enumerate() {
+                        table.assign_cell(
+                            || format!("{} {}", name, value),
+                            column,
+                            offset,
+                            || Value::known(F::from(value)),
+                        )?;
+                    }
+                    Ok(())
+                },
+            )?;
+        }
+
+        Ok(())
+    }
+}
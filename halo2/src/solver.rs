@@ -0,0 +1,287 @@
+//! A native sudoku solver, for finding the private witness to a puzzle.
+//!
+//! Solving alternates constraint propagation, which fills in every cell forced by the cells
+//! around it (a cell with a single candidate digit, or a digit with a single possible cell in
+//! some row, column or box), with backtracking on the cell with the fewest candidates whenever
+//! propagation gets stuck. Even the puzzles built to defeat naive backtracking take
+//! milliseconds.
+
+use crate::{isqrt, Puzzle, Solution};
+use ndarray::Array2;
+use std::fmt;
+
+/// The reasons a puzzle can fail to have a unique solution.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SolveError {
+    NoSolution,
+    MultipleSolutions,
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::NoSolution => write!(f, "the puzzle has no solution"),
+            SolveError::MultipleSolutions => write!(f, "the puzzle has more than one solution"),
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+// Grid is a partially filled board, along with the digits placed so far in each row, column
+// and box. Digit `d` is bit `d - 1` of each mask
+#[derive(Clone)]
+struct Grid<const N: usize> {
+    cells: Vec<u8>,
+    rows: Vec<u64>,
+    cols: Vec<u64>,
+    boxes: Vec<u64>,
+}
+
+impl<const N: usize> Grid<N> {
+    const BOX_SIZE: usize = {
+        let box_size = isqrt(N);
+        assert!(
+            box_size * box_size == N,
+            "the board size must be a perfect square"
+        );
+        box_size
+    };
+
+    const ALL_DIGITS: u64 = {
+        assert!(N < 64, "the solver handles boards of up to 63x63 cells");
+        (1 << N) - 1
+    };
+
+    fn box_of(index: usize) -> usize {
+        let (row, col) = (index / N, index % N);
+        row / Self::BOX_SIZE * Self::BOX_SIZE + col / Self::BOX_SIZE
+    }
+
+    // new places the clues of `puzzle`, returning `None` if any two of them conflict
+    fn new(puzzle: &Puzzle<N>) -> Option<Self> {
+        let mut grid = Grid {
+            cells: vec![0; N * N],
+            rows: vec![0; N],
+            cols: vec![0; N],
+            boxes: vec![0; N],
+        };
+        for (index, &clue) in puzzle.cells().iter().enumerate() {
+            if clue != 0 {
+                if grid.candidates(index) & (1 << (clue - 1)) == 0 {
+                    return None;
+                }
+                grid.place(index, clue);
+            }
+        }
+
+        Some(grid)
+    }
+
+    // candidates returns the digits that can still go in the cell at `index`
+    fn candidates(&self, index: usize) -> u64 {
+        let used = self.rows[index / N] | self.cols[index % N] | self.boxes[Self::box_of(index)];
+        !used & Self::ALL_DIGITS
+    }
+
+    fn place(&mut self, index: usize, digit: u8) {
+        let bit = 1 << (digit - 1);
+        self.cells[index] = digit;
+        self.rows[index / N] |= bit;
+        self.cols[index % N] |= bit;
+        self.boxes[Self::box_of(index)] |= bit;
+    }
+
+    // propagate fills in forced cells until there are none left, returning false if it
+    // finds a cell or a digit with nowhere to go
+    fn propagate(&mut self, groups: &[Vec<usize>]) -> bool {
+        loop {
+            let mut progress = false;
+
+            // naked singles: cells with only one candidate
+            for index in 0..N * N {
+                if self.cells[index] != 0 {
+                    continue;
+                }
+                let candidates = self.candidates(index);
+                match candidates.count_ones() {
+                    0 => return false,
+                    1 => {
+                        self.place(index, candidates.trailing_zeros() as u8 + 1);
+                        progress = true;
+                    }
+                    _ => {}
+                }
+            }
+
+            // hidden singles: digits with only one possible cell in a group
+            for group in groups {
+                for digit in 1..=N as u8 {
+                    let bit = 1 << (digit - 1);
+                    if group.iter().any(|&index| self.cells[index] == digit) {
+                        continue;
+                    }
+                    let mut cells = group.iter().filter(|&&index| {
+                        self.cells[index] == 0 && self.candidates(index) & bit != 0
+                    });
+                    match (cells.next(), cells.next()) {
+                        (None, _) => return false,
+                        (Some(&index), None) => {
+                            self.place(index, digit);
+                            progress = true;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            if !progress {
+                return true;
+            }
+        }
+    }
+}
+
+// cell_groups returns the indices of the cells of every row, then every column, then every
+// box, numbering the cells of the board in row-major order
+pub(crate) fn cell_groups<const N: usize>() -> Vec<Vec<usize>> {
+    let mut groups = vec![vec![]; 3 * N];
+    for index in 0..N * N {
+        groups[index / N].push(index);
+        groups[N + index % N].push(index);
+        groups[2 * N + Grid::<N>::box_of(index)].push(index);
+    }
+    groups
+}
+
+// search collects solutions reachable from `grid` into `solutions`, until there are `limit`
+// of them
+fn search<const N: usize>(
+    mut grid: Grid<N>,
+    groups: &[Vec<usize>],
+    limit: usize,
+    solutions: &mut Vec<Solution<N>>,
+) {
+    if solutions.len() >= limit || !grid.propagate(groups) {
+        return;
+    }
+
+    let open_cell = (0..N * N)
+        .filter(|&index| grid.cells[index] == 0)
+        .min_by_key(|&index| grid.candidates(index).count_ones());
+    let index = match open_cell {
+        Some(index) => index,
+        None => {
+            // every cell was placed without conflicts, so the board is a valid solution
+            let cells = Array2::from_shape_vec((N, N), grid.cells).expect("there are N*N cells");
+            solutions.push(Solution::new_unchecked(cells));
+            return;
+        }
+    };
+
+    let mut candidates = grid.candidates(index);
+    while candidates != 0 && solutions.len() < limit {
+        let digit = candidates.trailing_zeros() as u8 + 1;
+        candidates &= candidates - 1;
+
+        let mut guess = grid.clone();
+        guess.place(index, digit);
+        search(guess, groups, limit, solutions);
+    }
+}
+
+/// Finds up to `limit` solutions to `puzzle`.
+pub fn solutions<const N: usize>(puzzle: &Puzzle<N>, limit: usize) -> Vec<Solution<N>> {
+    let mut solutions = vec![];
+    if let Some(grid) = Grid::new(puzzle) {
+        search(grid, &cell_groups::<N>(), limit, &mut solutions);
+    }
+    solutions
+}
+
+/// Solves `puzzle`, failing unless it has exactly one solution.
+pub fn solve<const N: usize>(puzzle: &Puzzle<N>) -> Result<Solution<N>, SolveError> {
+    match solutions(puzzle, 2).as_slice() {
+        [] => Err(SolveError::NoSolution),
+        [solution] => Ok(solution.clone()),
+        _ => Err(SolveError::MultipleSolutions),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::PuzzleReader;
+
+    // is_valid_solution checks every row, column and box of `solution` natively
+    fn is_valid_solution<const N: usize>(solution: &Solution<N>) -> bool {
+        cell_groups::<N>().iter().all(|group| {
+            let digits = group.iter().fold(0u64, |digits, &index| {
+                digits | 1 << (solution.cells()[[index / N, index % N]] - 1)
+            });
+            digits == Grid::<N>::ALL_DIGITS
+        })
+    }
+
+    #[test]
+    fn test_solve_hard_puzzles() {
+        let corpus = include_str!("../puzzles/hard.txt");
+        let mut solved = 0;
+        for puzzle in PuzzleReader::<_, 9>::new(corpus.as_bytes()) {
+            let puzzle = puzzle.unwrap();
+            let solution = solve(&puzzle).unwrap();
+            assert!(is_valid_solution(&solution));
+            assert_eq!(solution.check_matches(&puzzle), Ok(()));
+            solved += 1;
+        }
+        assert!(solved > 0);
+    }
+
+    #[test]
+    fn test_solve_other_sizes() {
+        let puzzle: Puzzle<4> = "1..4.41.2..3.32.".parse().unwrap();
+        assert_eq!(solve(&puzzle).unwrap().to_string(), "1234341221434321");
+
+        // a blank board is solved by search alone
+        let solution = &solutions(&Puzzle::<16>::blank(), 1)[0];
+        assert!(is_valid_solution(solution));
+    }
+
+    #[test]
+    fn test_no_solution() {
+        // two clues in the same row conflict
+        let mut cells = Array2::zeros((9, 9));
+        cells[[0, 0]] = 1;
+        cells[[0, 8]] = 1;
+        let puzzle = Puzzle::new(cells).unwrap();
+        assert_eq!(solve(&puzzle), Err(SolveError::NoSolution));
+
+        // the top right cell can only be a 9, but there's a 9 below it
+        let mut cells = Array2::zeros((9, 9));
+        for col in 0..8 {
+            cells[[0, col]] = col as u8 + 1;
+        }
+        cells[[4, 8]] = 9;
+        let puzzle = Puzzle::new(cells).unwrap();
+        assert_eq!(solve(&puzzle), Err(SolveError::NoSolution));
+    }
+
+    #[test]
+    fn test_multiple_solutions() {
+        assert_eq!(
+            solve(&Puzzle::<9>::blank()),
+            Err(SolveError::MultipleSolutions)
+        );
+
+        // the 1s and 3s in the first and third columns of the top two rows of this solution
+        // can be swapped, so blanking those four cells leaves a puzzle with two solutions
+        let solution: Solution<4> = "1234341221434321".parse().unwrap();
+        let mut cells = solution.cells().clone();
+        for (row, col) in [(0, 0), (0, 2), (1, 0), (1, 2)] {
+            cells[[row, col]] = 0;
+        }
+        let puzzle = Puzzle::<4>::new(cells).unwrap();
+        assert_eq!(solutions(&puzzle, 3).len(), 2);
+        assert_eq!(solve(&puzzle), Err(SolveError::MultipleSolutions));
+    }
+}
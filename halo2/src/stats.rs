@@ -0,0 +1,356 @@
+//! Row and column counts for [`SudokuCircuit`], for sizing parameters and for comparing the
+//! cost of the circuit's strategies.
+//!
+//! The circuit is laid out by its floor planner against a recorder that only notes which rows
+//! each region assigns to, so no values are computed and no particular `k` is needed. Row
+//! usage doesn't depend on the boards, so the stats of a blank circuit hold for every puzzle.
+
+use crate::{
+    commitment::PuzzleCommitmentKind,
+    diagnostics::{from_cell_region_name, group_of, Group},
+    group::GroupCheckStrategy,
+    public_inputs_with_commitment,
+    range::RangeCheckKind,
+    SudokuCircuit, HASH_PUZZLE_REGION, LOAD_BOARDS_REGION,
+};
+use halo2wrong::halo2::{
+    arithmetic::FieldExt,
+    circuit::Value,
+    plonk::{
+        Advice, Any, Assigned, Assignment, Challenge, Circuit, Column, ConstraintSystem, Error,
+        Fixed, FloorPlanner, Instance, Selector,
+    },
+};
+use std::{cmp, fmt, ops::Range};
+
+/// The number of rows a single region takes up.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegionStats {
+    pub name: String,
+    pub rows: usize,
+}
+
+/// How many rows each phase of synthesis uses, and what the circuit needs of the parameters
+/// it is proven with.
+///
+/// A phase's rows are the summed heights of its regions. Regions over different columns can
+/// be laid out side by side, so the phases may add up to more than `rows`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CircuitStats {
+    /// Loading the puzzle and the solution into the board column, range checking them
+    pub board_loading: usize,
+    /// Checking that the solution agrees with every clue of the puzzle
+    pub puzzle_matching: usize,
+    /// Hashing the puzzle, if it is exposed as a Poseidon hash
+    pub puzzle_hashing: usize,
+    pub row_checks: usize,
+    pub column_checks: usize,
+    pub box_checks: usize,
+    /// Lookup tables, and regions shared by every group check
+    pub other: usize,
+    /// Every region, in the order it was laid out
+    pub regions: Vec<RegionStats>,
+
+    /// The number of rows used, up to the last one assigned to
+    pub rows: usize,
+    pub advice_columns: usize,
+    /// Fixed columns, not counting the ones selectors are turned into at key generation
+    pub fixed_columns: usize,
+    pub selectors: usize,
+    pub instance_columns: usize,
+    /// The max degree of the circuit's constraints, before selectors are combined
+    pub max_degree: usize,
+    /// The smallest `k` such that the circuit, its public inputs and its blinding rows fit in
+    /// 2^k rows
+    pub min_k: u32,
+}
+
+impl CircuitStats {
+    /// Lays `circuit` out and measures it.
+    pub fn measure<F, const N: usize, R, C, G>(
+        circuit: &SudokuCircuit<F, N, R, C, G>,
+    ) -> Result<Self, Error>
+    where
+        F: FieldExt,
+        R: RangeCheckKind,
+        C: PuzzleCommitmentKind,
+        G: GroupCheckStrategy,
+    {
+        let mut cs = ConstraintSystem::default();
+        let config = SudokuCircuit::<F, N, R, C, G>::configure(&mut cs);
+        let mut recorder = RowRecorder::default();
+        <SudokuCircuit<F, N, R, C, G> as Circuit<F>>::FloorPlanner::synthesize(
+            &mut recorder,
+            circuit,
+            config,
+            cs.constants().clone(),
+        )?;
+
+        let instance_rows = public_inputs_with_commitment(circuit.puzzle(), None::<F>, C::MODE)
+            .iter()
+            .map(Vec::len)
+            .max()
+            .unwrap_or_default();
+        // MockProver and the real prover both keep the last `blinding_factors() + 1` rows
+        // for themselves, and need at least `minimum_rows()` rows in all
+        let needed_rows = cmp::max(
+            cmp::max(recorder.rows, instance_rows) + cs.blinding_factors() + 1,
+            cs.minimum_rows(),
+        );
+        let min_k = usize::BITS - (needed_rows - 1).leading_zeros();
+
+        let mut stats = CircuitStats {
+            board_loading: 0,
+            puzzle_matching: 0,
+            puzzle_hashing: 0,
+            row_checks: 0,
+            column_checks: 0,
+            box_checks: 0,
+            other: 0,
+            regions: vec![],
+            rows: recorder.rows,
+            advice_columns: cs.num_advice_columns(),
+            fixed_columns: cs.num_fixed_columns(),
+            selectors: cs.num_selectors(),
+            instance_columns: cs.num_instance_columns(),
+            max_degree: cs.degree(),
+            min_k,
+        };
+
+        // the cells' and groups' regions are named after them
+        for (name, rows) in recorder.regions {
+            let rows = rows.map_or(0, |rows| rows.len());
+            let phase = match name.as_str() {
+                LOAD_BOARDS_REGION => &mut stats.board_loading,
+                HASH_PUZZLE_REGION => &mut stats.puzzle_hashing,
+                name if from_cell_region_name(name).is_some() => &mut stats.puzzle_matching,
+                name => match group_of(name) {
+                    Some(Group::Row(_)) => &mut stats.row_checks,
+                    Some(Group::Column(_)) => &mut stats.column_checks,
+                    Some(Group::Box(..)) => &mut stats.box_checks,
+                    None => &mut stats.other,
+                },
+            };
+            *phase += rows;
+            stats.regions.push(RegionStats { name, rows });
+        }
+
+        Ok(stats)
+    }
+}
+
+impl fmt::Display for CircuitStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "rows by phase:")?;
+        for (phase, rows) in [
+            ("board loading", self.board_loading),
+            ("puzzle matching", self.puzzle_matching),
+            ("puzzle hashing", self.puzzle_hashing),
+            ("row checks", self.row_checks),
+            ("column checks", self.column_checks),
+            ("box checks", self.box_checks),
+            ("other", self.other),
+        ] {
+            writeln!(f, "  {:<18}{:>6}", phase, rows)?;
+        }
+        writeln!(f, "{:<20}{:>6}", "rows used", self.rows)?;
+        writeln!(f, "{:<20}{:>6}", "advice columns", self.advice_columns)?;
+        writeln!(f, "{:<20}{:>6}", "fixed columns", self.fixed_columns)?;
+        writeln!(f, "{:<20}{:>6}", "selectors", self.selectors)?;
+        writeln!(f, "{:<20}{:>6}", "instance columns", self.instance_columns)?;
+        writeln!(f, "{:<20}{:>6}", "max degree", self.max_degree)?;
+        write!(f, "{:<20}{:>6}", "min k", self.min_k)
+    }
+}
+
+// RowRecorder stands in for the prover during synthesis, noting the rows each region assigns
+// to and ignoring the values
+#[derive(Default)]
+struct RowRecorder {
+    // every region entered so far, with the rows it has assigned to if any
+    regions: Vec<(String, Option<Range<usize>>)>,
+    in_region: bool,
+    // one past the last row assigned to, in or out of a region
+    rows: usize,
+}
+
+impl RowRecorder {
+    fn use_row(&mut self, row: usize) {
+        self.rows = cmp::max(self.rows, row + 1);
+        if !self.in_region {
+            return;
+        }
+        if let Some((_, rows)) = self.regions.last_mut() {
+            *rows = Some(match rows.take() {
+                Some(rows) => cmp::min(rows.start, row)..cmp::max(rows.end, row + 1),
+                None => row..row + 1,
+            });
+        }
+    }
+}
+
+impl<F: FieldExt> Assignment<F> for RowRecorder {
+    fn enter_region<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.regions.push((name_fn().into(), None));
+        self.in_region = true;
+    }
+
+    fn exit_region(&mut self) {
+        self.in_region = false;
+    }
+
+    fn enable_selector<A, AR>(&mut self, _: A, _: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.use_row(row);
+        Ok(())
+    }
+
+    fn query_instance(&self, _: Column<Instance>, _: usize) -> Result<Value<F>, Error> {
+        Ok(Value::unknown())
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        _: Column<Advice>,
+        row: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.use_row(row);
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        _: Column<Fixed>,
+        row: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.use_row(row);
+        Ok(())
+    }
+
+    fn copy(&mut self, _: Column<Any>, _: usize, _: Column<Any>, _: usize) -> Result<(), Error> {
+        Ok(())
+    }
+
+    // filling the rest of a column uses no rows of its own
+    fn fill_from_row(
+        &mut self,
+        _: Column<Fixed>,
+        _: usize,
+        _: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn get_challenge(&self, _: Challenge) -> Value<F> {
+        Value::unknown()
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commitment::{PoseidonPuzzleHash, PuzzleCells},
+        group::{Fingerprint, IndexHint, MultisetProduct, PairwiseDistinct, Shuffle},
+        range::{LookupRangeCheck, PolynomialRangeCheck},
+        K, K_HASHED_PUZZLE,
+    };
+    use halo2wrong::halo2::{dev::MockProver, halo2curves::bn256::Fr as Fp};
+
+    // check_min_k checks that the mock prover lays the circuit out in 2^min_k rows, but not in
+    // half as many
+    fn check_min_k<R: RangeCheckKind, C: PuzzleCommitmentKind, G: GroupCheckStrategy>() {
+        let circuit = SudokuCircuit::<Fp, 9, R, C, G>::default();
+        let min_k = circuit.stats().unwrap().min_k;
+        let instances = public_inputs_with_commitment(&circuit.puzzle, circuit.solver_id, C::MODE);
+
+        assert!(MockProver::run(min_k, &circuit, instances.clone()).is_ok());
+        assert!(matches!(
+            MockProver::run(min_k - 1, &circuit, instances),
+            Err(Error::NotEnoughRowsAvailable { .. } | Error::InstanceTooLarge)
+        ));
+    }
+
+    #[test]
+    fn test_stats() {
+        let stats = SudokuCircuit::<Fp>::default().stats().unwrap();
+
+        // each board takes up one row of the board column per cell
+        assert_eq!(stats.board_loading, 2 * 81);
+        assert!(stats.puzzle_matching > 0);
+        assert_eq!(stats.puzzle_hashing, 0);
+
+        // every group is checked the same way
+        assert!(stats.row_checks > 0);
+        assert_eq!(stats.row_checks, stats.column_checks);
+        assert_eq!(stats.row_checks, stats.box_checks);
+
+        assert_eq!(stats.instance_columns, 3);
+        assert!(stats.min_k <= K);
+        assert!(stats.rows <= 1 << K);
+        assert_eq!(
+            stats
+                .regions
+                .iter()
+                .map(|region| region.rows)
+                .sum::<usize>(),
+            stats.board_loading
+                + stats.puzzle_matching
+                + stats.row_checks
+                + stats.column_checks
+                + stats.box_checks
+                + stats.other
+        );
+
+        let hashed = SudokuCircuit::<Fp, 9, PolynomialRangeCheck, PoseidonPuzzleHash>::default()
+            .stats()
+            .unwrap();
+        assert!(hashed.puzzle_hashing > 0);
+        assert!(hashed.min_k > stats.min_k);
+        assert!(hashed.min_k <= K_HASHED_PUZZLE);
+    }
+
+    // the stats must agree with the mock prover on how many rows the circuit needs
+    #[test]
+    fn test_stats_min_k() {
+        check_min_k::<PolynomialRangeCheck, PuzzleCells, PairwiseDistinct>();
+        check_min_k::<PolynomialRangeCheck, PuzzleCells, Fingerprint>();
+        check_min_k::<PolynomialRangeCheck, PuzzleCells, Shuffle>();
+        check_min_k::<PolynomialRangeCheck, PuzzleCells, IndexHint>();
+        check_min_k::<PolynomialRangeCheck, PuzzleCells, MultisetProduct>();
+        check_min_k::<LookupRangeCheck, PuzzleCells, PairwiseDistinct>();
+        check_min_k::<PolynomialRangeCheck, PoseidonPuzzleHash, PairwiseDistinct>();
+    }
+}
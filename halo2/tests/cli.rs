@@ -0,0 +1,117 @@
+//! Runs the `sudoku-halo2` binary end to end, checking the exit codes shell scripts rely on.
+
+use std::{
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+// The example puzzle shared with the other tests, followed by its solution
+const EXAMPLE: &str = include_str!("../puzzles/example.txt");
+
+// sudoku_halo2 runs the binary with the given arguments and returns its exit code
+fn sudoku_halo2<I, S>(args: I) -> i32
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    Command::new(env!("CARGO_BIN_EXE_sudoku-halo2"))
+        .args(args)
+        .output()
+        .expect("the binary should run")
+        .status
+        .code()
+        .expect("the binary should exit rather than be killed")
+}
+
+// scratch_dir returns an empty directory for this test's files
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sudoku-halo2-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn write(dir: &Path, name: &str, contents: &str) -> PathBuf {
+    let path = dir.join(name);
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn test_keygen_prove_verify() {
+    let dir = scratch_dir("round-trip");
+    let keys = dir.join("keys");
+    let vk = keys.join("vk.bin");
+    let proof = dir.join("proof.bin");
+    // the example file starts with comments, which are skipped
+    let puzzle = Path::new(env!("CARGO_MANIFEST_DIR")).join("puzzles/example.txt");
+    let solution_line = EXAMPLE.lines().filter(|line| !line.starts_with('#')).nth(1);
+    let solution = write(&dir, "s.txt", solution_line.unwrap());
+
+    assert_eq!(
+        sudoku_halo2([OsStr::new("keygen"), "--out".as_ref(), keys.as_ref()]),
+        0
+    );
+
+    let prove = |puzzle: &Path, solution: &Path| {
+        sudoku_halo2([
+            OsStr::new("prove"),
+            "--puzzle".as_ref(),
+            puzzle.as_ref(),
+            "--solution".as_ref(),
+            solution.as_ref(),
+            "--vk".as_ref(),
+            vk.as_ref(),
+            "--out".as_ref(),
+            proof.as_ref(),
+        ])
+    };
+    let verify = |puzzle: &Path| {
+        sudoku_halo2([
+            OsStr::new("verify"),
+            "--puzzle".as_ref(),
+            puzzle.as_ref(),
+            "--vk".as_ref(),
+            vk.as_ref(),
+            "--proof".as_ref(),
+            proof.as_ref(),
+        ])
+    };
+
+    assert_eq!(prove(&puzzle, &solution), 0);
+    assert_eq!(verify(&puzzle), 0);
+
+    // a tampered proof doesn't verify
+    let mut tampered = fs::read(&proof).unwrap();
+    let last = tampered.len() - 1;
+    tampered[last] ^= 1;
+    fs::write(&proof, tampered).unwrap();
+    assert_eq!(verify(&puzzle), 1);
+
+    // boards that are malformed, missing from their file, or whose file is missing are bad
+    // input
+    let malformed = write(&dir, "malformed.txt", "12345");
+    let empty = write(&dir, "empty.txt", "# no puzzle here\n\n");
+    let missing = dir.join("missing.txt");
+    assert_eq!(prove(&malformed, &solution), 3);
+    assert_eq!(prove(&puzzle, &malformed), 3);
+    assert_eq!(prove(&empty, &solution), 3);
+    assert_eq!(prove(&missing, &solution), 3);
+    assert_eq!(verify(&missing), 3);
+
+    // parameters too small for the circuit are rejected before running keygen
+    assert_eq!(
+        sudoku_halo2([
+            OsStr::new("keygen"),
+            "--k".as_ref(),
+            "11".as_ref(),
+            "--out".as_ref(),
+            dir.join("small").as_ref(),
+        ]),
+        3
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}